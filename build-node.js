@@ -0,0 +1,10 @@
+import { copyFile } from "node:fs/promises";
+
+// name of the cdylib built by cargo on each platform
+const libraries = {
+  darwin: "libremove_exports.dylib",
+  win32: "remove_exports.dll",
+};
+const library = libraries[process.platform] ?? "libremove_exports.so";
+
+await copyFile(`./target/release/${library}`, "./remove_exports.node");
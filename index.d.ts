@@ -1 +1,179 @@
+/** Error thrown when a module cannot be transformed. */
+export interface RemoveExportsError extends Error {
+  code:
+    | "PARSE_ERROR"
+    | "UNSUPPORTED_SYNTAX"
+    | "CODEGEN_ERROR"
+    | "SOURCE_MAP_ERROR"
+    | "INVALID_PATTERN"
+    | "INVALID_DEFINE"
+    | "LOAD_ERROR"
+    | "INVALID_OPTIONS";
+  /** String indices (UTF-16 offsets) of the offending span, absent for codegen errors. */
+  start?: number;
+  end?: number;
+  /** 1-based line number. */
+  line?: number;
+  /** 0-based column number. */
+  column?: number;
+}
+
+export type EsVersion =
+  | "es3"
+  | "es5"
+  | "es2015"
+  | "es2016"
+  | "es2017"
+  | "es2018"
+  | "es2019"
+  | "es2020"
+  | "es2021"
+  | "es2022"
+  | "esnext";
+
+export interface Options {
+  /** Remove the given exports, or keep them and remove every other one. */
+  mode?: "remove" | "keep";
+  /** Globs (`use*`) or regexes wrapped in slashes (`/^use[A-Z]/`) matching export names. */
+  patterns?: string[];
+  /** Remove top-level statements that only use removed declarations. */
+  pruneStatements?: boolean;
+  /** Remove unused side-effect free declarations inside kept functions, and the imports only they used. */
+  pruneLocals?: boolean;
+  /** Global expressions replaced with constant expressions, e.g. `{ "import.meta.env.SSR": "false" }`. */
+  define?: Record<string, string>;
+  /** Globs of modules kept as `import "x"` when all their imported specifiers are removed. */
+  sideEffects?: string[];
+  /** Globs of modules always dropped when all their imported specifiers are removed. */
+  sideEffectFree?: string[];
+  /** Parse as a CommonJS script and remove `exports.foo = ...`, `module.exports = { ... }` and `Object.defineProperty(exports, ...)` exports. */
+  commonjs?: boolean;
+  /** Remove `using` and `await using` declarations only used by removed code, instead of keeping them for their disposal. */
+  dropDisposables?: boolean;
+  /** Module name, used in source maps and errors, and to infer `syntax`. */
+  filename?: string;
+  /** Source language, inferred from `filename` by default. */
+  syntax?: "js" | "jsx" | "ts" | "tsx";
+  /** ECMAScript version of the output, defaults to `"es2022"`. */
+  target?: EsVersion;
+  /** Newest ECMAScript version accepted in the source, defaults to `"esnext"`. */
+  inputTarget?: EsVersion;
+  /** Lower syntax newer than `target` with swc's compat transforms, JavaScript only. */
+  downlevel?: boolean;
+  /** Parse JSX regardless of the language. */
+  jsx?: boolean;
+  /** Generate a v3 source map. */
+  sourceMaps?: boolean;
+  /** Source map of a previous transform to chain onto. */
+  inputSourceMap?: string;
+  /** Emit minified code. */
+  minify?: boolean;
+  /** Run swc's compressor over the pruned module, JavaScript without JSX only. */
+  compress?: boolean;
+  /** Shorten the names of local bindings, exports are kept. JavaScript without JSX only. */
+  mangle?: boolean;
+  /** Which comments to keep, `"license"` keeps `/*! ... *\/` and `@license` ones, defaults to `"all"` (`true`). */
+  comments?: "all" | "license" | "none" | boolean;
+  /** Splice the original source instead of printing the whole module, leaving untouched code as is. Cannot be combined with `minify`, `compress`, `mangle` or `downlevel`. */
+  preserveFormat?: boolean;
+}
+
+export interface Report {
+  /** Export names that were removed. */
+  removedExports: string[];
+  /** Module-level declarations that were removed. */
+  removedDecls: string[];
+  /** Removed import specifiers by source module, `default` and `*` for default and namespace imports. */
+  removedImports: Record<string, string[]>;
+  /** Requested export names that were not found in the module. */
+  notFound: string[];
+}
+
+/** A replacement of a range of the original source. */
+export interface Edit {
+  /** String indices (UTF-16 offsets) of the replaced range. */
+  start: number;
+  end: number;
+  /** Replacement text, empty for removals. */
+  text: string;
+}
+
+export interface Output {
+  code: string;
+  map: string | null;
+  report: Report;
+  /** Edits turning the source into `code`, if `preserveFormat` is enabled and the module did not have to be printed whole. */
+  edits: Edit[] | null;
+}
+
+export interface Import {
+  source: string;
+  /** Imported name, `default` and `*` for default and namespace imports. */
+  imported: string;
+}
+
+export interface Export {
+  /** Exported name, `*` for `export * from "..."`. */
+  name: string;
+  kind: "decl" | "ref" | "default" | "re-export" | "star";
+  /** Module-level declarations it binds or references. */
+  locals: string[];
+  /** Source module of re-exports. */
+  source: string | null;
+  /** Imports it transitively depends on. */
+  imports: Import[];
+}
+
+export interface Declaration {
+  /** Where the binding is imported from, if it is an import. */
+  import: Import | null;
+  /** Other module-level declarations it references directly. */
+  refs: string[];
+  /** Imports it transitively depends on. */
+  imports: Import[];
+}
+
+export interface Analysis {
+  exports: Export[];
+  declarations: Record<string, Declaration>;
+  /** Declarations referenced by top-level statements, which are always kept. */
+  sideEffectRefs: string[];
+}
+
+export interface Loader {
+  /** Resolve a specifier to a module id, `null` to not follow it. */
+  resolve(specifier: string, importer: string): string | null | undefined;
+  /** Source code of a module. */
+  load(id: string): string;
+}
+
+export interface Job {
+  /** Module name, replaces the `filename` option. */
+  filename: string;
+  source: string;
+  exports: string[];
+  /** Source map of a previous transform of this module. */
+  inputSourceMap?: string;
+}
+
+/** Removes exports from many modules, caching their analysis between runs. */
+export class Batch {
+  constructor(options?: Options);
+  /** Outputs of `jobs` in the same order, or the errors they failed with. `options.inputSourceMap` is rejected, set it on each job. */
+  run(jobs: Job[]): (Output | RemoveExportsError)[];
+  free(): void;
+}
+
 export const remove_exports: (source: string, exports: string[]) => string;
+export const remove_exports_with_options: (
+  source: string,
+  exports: string[],
+  options?: Options
+) => Output;
+export const analyze: (source: string, options?: Options) => Analysis;
+export const remove_exports_from_graph: (
+  entry: string,
+  exports: string[],
+  options: Options | null | undefined,
+  loader: Loader
+) => Record<string, Output>;
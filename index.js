@@ -1,5 +1,17 @@
-import init, { remove_exports } from "./pkg/remove_exports.js";
+import init, {
+  analyze,
+  Batch,
+  remove_exports,
+  remove_exports_from_graph,
+  remove_exports_with_options,
+} from "./pkg/remove_exports.js";
 
 await init();
 
-export { remove_exports };
+export {
+  analyze,
+  Batch,
+  remove_exports,
+  remove_exports_from_graph,
+  remove_exports_with_options,
+};
@@ -1,4 +1,11 @@
-import { remove_exports } from "./index.js";
+import {
+  analyze,
+  Batch,
+  remove_exports,
+  remove_exports_from_graph,
+  remove_exports_with_options,
+} from "./index.js";
+import { existsSync } from "node:fs";
 import test from "node:test";
 import { equal as assertEquals, throws as assertThrows } from "node:assert";
 
@@ -28,3 +35,80 @@ test("should throw on invalid code", () => {
   assertThrows(() => remove_exports(code, []));
   assertEquals(remove_exports(`export const foo = bar();`, ["foo"]), "");
 });
+
+test("should report error location", () => {
+  assertThrows(() => remove_exports("const a = 1;\nexport {", []), {
+    code: "PARSE_ERROR",
+    line: 2,
+    column: 7,
+  });
+});
+
+test("should accept options", () => {
+  const code = `export const foo = 1;\nexport const bar: number = 2;\n`;
+  const result = remove_exports_with_options(code, ["foo"], {
+    filename: "route.ts",
+    sourceMaps: true,
+  });
+
+  assertEquals(result.code, "export const bar: number = 2;\n");
+  assertEquals(JSON.parse(result.map).sources[0], "route.ts");
+});
+
+test("should analyze exports", () => {
+  const code = `import { json } from "router";\nexport const loader = () => json();\n`;
+  const { exports } = analyze(code);
+
+  assertEquals(exports[0].name, "loader");
+  assertEquals(exports[0].kind, "decl");
+  assertEquals(exports[0].imports[0].source, "router");
+});
+
+test("should follow re-exports", () => {
+  const modules = {
+    "route.js": `export { loader } from "./server";\nexport default 1;\n`,
+    "server.js": `export const loader = () => {};\nexport const meta = {};\n`,
+  };
+  const outputs = remove_exports_from_graph("route.js", ["loader"], null, {
+    resolve: (specifier) => specifier.replace("./", "") + ".js",
+    load: (id) => modules[id],
+  });
+
+  assertEquals(outputs["route.js"].code, "export default 1;\n");
+  assertEquals(outputs["server.js"].code, "export const meta = {};\n");
+});
+
+test("should transform a batch", () => {
+  const batch = new Batch({});
+  const jobs = [
+    { filename: "a.js", source: "export const a = 1, b = 2;", exports: ["a"] },
+    { filename: "b.js", source: "export {", exports: [] },
+  ];
+
+  for (let i = 0; i < 2; i++) {
+    const [output, error] = batch.run(jobs);
+    assertEquals(output.code, "export const b = 2;\n");
+    assertEquals(error.code, "PARSE_ERROR");
+  }
+});
+
+// the native addon is only there after `npm run build:node`
+const native = existsSync(new URL("./remove_exports.node", import.meta.url));
+
+test("should work with the native addon", { skip: !native }, async () => {
+  const node = await import("./node.js");
+  const code = `export const foo = 1;\nexport const bar = 2;\n`;
+
+  assertEquals(node.remove_exports(code, ["foo"]), "export const bar = 2;\n");
+  assertEquals(
+    node.remove_exports(Buffer.from(code), ["bar"]),
+    "export const foo = 1;\n"
+  );
+  assertThrows(() => node.remove_exports("export {", []), {
+    code: "PARSE_ERROR",
+  });
+
+  const batch = new node.Batch({});
+  const [output] = batch.run([{ filename: "a.js", source: code, exports: ["foo"] }]);
+  assertEquals(output.code, "export const bar = 2;\n");
+});
@@ -0,0 +1,41 @@
+/// <reference types="node" />
+import type { Analysis, Job, Loader, Options, Output, RemoveExportsError } from "./index.js";
+
+export type {
+  Analysis,
+  Declaration,
+  Edit,
+  EsVersion,
+  Export,
+  Import,
+  Job,
+  Loader,
+  Options,
+  Output,
+  RemoveExportsError,
+  Report,
+} from "./index.js";
+
+/** Source code, `Buffer`s are read in place instead of being copied. */
+export type Source = string | Buffer;
+
+/** Removes exports from many modules, caching their analysis between runs. */
+export class Batch {
+  constructor(options?: Options);
+  /** Outputs of `jobs` in the same order, or the errors they failed with. `options.inputSourceMap` is rejected, set it on each job. */
+  run(jobs: Job[]): (Output | RemoveExportsError)[];
+}
+
+export const remove_exports: (source: Source, exports: string[]) => string;
+export const remove_exports_with_options: (
+  source: Source,
+  exports: string[],
+  options?: Options
+) => Output;
+export const analyze: (source: Source, options?: Options) => Analysis;
+export const remove_exports_from_graph: (
+  entry: string,
+  exports: string[],
+  options: Options | null | undefined,
+  loader: Loader
+) => Record<string, Output>;
@@ -0,0 +1,18 @@
+import { createRequire } from "node:module";
+
+// native addon built by `npm run build:node`
+const {
+  analyze,
+  Batch,
+  remove_exports,
+  remove_exports_from_graph,
+  remove_exports_with_options,
+} = createRequire(import.meta.url)("./remove_exports.node");
+
+export {
+  analyze,
+  Batch,
+  remove_exports,
+  remove_exports_from_graph,
+  remove_exports_with_options,
+};
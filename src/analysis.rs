@@ -0,0 +1,179 @@
+//! Analysis of a module's exports, without transforming it.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use serde::Serialize;
+use swc_ecmascript::ast::Id;
+
+use crate::visitor::{ImportFrom, ImportVisitor};
+
+/// How a name is exported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExportKind {
+  /// `export const foo = ...`
+  Decl,
+  /// `export { foo as bar }`
+  Ref,
+  /// `export default ...`
+  Default,
+  /// `export { foo } from "source"`
+  ReExport,
+  /// `export * from "source"`
+  Star,
+}
+
+/// An import a declaration or export depends on.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Import {
+  /// The source module.
+  pub source: String,
+  /// Imported name, `default` and `*` for default and namespace imports.
+  pub imported: String,
+}
+
+impl From<&ImportFrom> for Import {
+  fn from(import: &ImportFrom) -> Self {
+    Self {
+      source: import.src.clone(),
+      imported: import.imported.clone(),
+    }
+  }
+}
+
+/// An export of the module.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Export {
+  /// Exported name, `*` for `export * from "source"`.
+  pub name: String,
+  pub kind: ExportKind,
+  /// Module-level declarations it binds or references.
+  pub locals: Vec<String>,
+  /// Source module of re-exports.
+  pub source: Option<String>,
+  /// Imports it transitively depends on.
+  pub imports: Vec<Import>,
+}
+
+/// A module-level declaration, including imported bindings.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Declaration {
+  /// Where the binding is imported from, if it is an import.
+  pub import: Option<Import>,
+  /// Other module-level declarations it references directly.
+  pub refs: Vec<String>,
+  /// Imports it transitively depends on.
+  pub imports: Vec<Import>,
+}
+
+/// Exports of a module and their dependency graph.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Analysis {
+  /// Every export, in name order, followed by `export *` ones.
+  pub exports: Vec<Export>,
+  /// Every module-level declaration by name.
+  pub declarations: BTreeMap<String, Declaration>,
+  /// Declarations referenced by top-level statements, which are always kept.
+  pub side_effect_refs: Vec<String>,
+}
+
+impl Analysis {
+  pub(crate) fn new(imports: &ImportVisitor) -> Self {
+    let name = |id: &Id| id.0.to_string();
+    let locals = |ids: &mut dyn Iterator<Item = &Id>| {
+      ids
+        .filter(|id| imports.decl_refs.contains_key(*id))
+        .map(name)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>()
+    };
+
+    // every import reachable from `roots`
+    let reachable = |roots: &mut dyn Iterator<Item = &Id>| {
+      let mut seen = BTreeSet::new();
+      let mut queue = roots.cloned().collect::<VecDeque<_>>();
+      let mut result = BTreeSet::new();
+      while let Some(id) = queue.pop_front() {
+        if !seen.insert(id.clone()) {
+          continue;
+        }
+        if let Some(import) = imports.imports.get(&id) {
+          result.insert(Import::from(import));
+        }
+        if let Some(refs) = imports.decl_refs.get(&id) {
+          queue.extend(refs.iter().cloned());
+        }
+      }
+      result.into_iter().collect::<Vec<_>>()
+    };
+
+    let mut exports = vec![];
+    for (name, id) in &imports.export_decls {
+      exports.push(Export {
+        name: name.clone(),
+        kind: ExportKind::Decl,
+        locals: locals(&mut [id].into_iter()),
+        source: None,
+        imports: reachable(&mut [id].into_iter()),
+      });
+    }
+    for (name, ids) in &imports.export_refs {
+      exports.push(Export {
+        name: name.clone(),
+        kind: match name.as_str() {
+          "default" => ExportKind::Default,
+          _ => ExportKind::Ref,
+        },
+        locals: locals(&mut ids.iter()),
+        source: None,
+        imports: reachable(&mut ids.iter()),
+      });
+    }
+    for (name, from) in &imports.export_froms {
+      exports.push(Export {
+        name: name.clone(),
+        kind: ExportKind::ReExport,
+        locals: vec![],
+        source: Some(from.src.clone()),
+        imports: vec![Import {
+          source: from.src.clone(),
+          imported: from.orig.clone(),
+        }],
+      });
+    }
+    exports.sort_by(|a, b| a.name.cmp(&b.name));
+    for src in &imports.export_alls {
+      exports.push(Export {
+        name: "*".to_string(),
+        kind: ExportKind::Star,
+        locals: vec![],
+        source: Some(src.clone()),
+        imports: vec![Import {
+          source: src.clone(),
+          imported: "*".to_string(),
+        }],
+      });
+    }
+
+    let declarations = imports
+      .decl_refs
+      .iter()
+      .map(|(id, refs)| {
+        let declaration = Declaration {
+          import: imports.imports.get(id).map(Import::from),
+          refs: locals(&mut refs.iter().filter(|x| *x != id)),
+          imports: reachable(&mut [id].into_iter()),
+        };
+        (name(id), declaration)
+      })
+      .collect();
+
+    Self {
+      exports,
+      declarations,
+      side_effect_refs: locals(&mut imports.global_refs.iter()),
+    }
+  }
+}
@@ -0,0 +1,211 @@
+//! Transforming many modules at once.
+
+use std::{
+  cell::RefCell,
+  collections::{hash_map::DefaultHasher, HashMap},
+  hash::{Hash, Hasher},
+  rc::Rc,
+  sync::{Arc, Mutex},
+};
+
+use serde::Deserialize;
+use swc_common::{
+  comments::{SingleThreadedComments, SingleThreadedCommentsMapInner},
+  FileName, SourceMap,
+};
+use swc_ecmascript::ast::Module;
+
+use crate::{parse, source_map, transform, Error, ImportVisitor, Options, Output, Parsed};
+
+/// A module to remove exports from.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Job {
+  /// Module name, replaces the `filename` option.
+  pub filename: String,
+  pub source: String,
+  pub exports: Vec<String>,
+  /// Source map of a previous transform of this module, replaces the
+  /// `input_source_map` option.
+  #[serde(default)]
+  pub input_source_map: Option<String>,
+}
+
+/// A parsed and analyzed module that can be shared between threads.
+///
+/// Spans are only meaningful in a source map holding nothing but the module
+/// source, and syntax contexts are only compared, never resolved, once the
+/// analysis is done.
+struct Analyzed {
+  module: Module,
+  original: Option<Module>,
+  leading: SingleThreadedCommentsMapInner,
+  trailing: SingleThreadedCommentsMapInner,
+  import: ImportVisitor,
+}
+
+impl Analyzed {
+  fn new(parsed: &Parsed) -> Self {
+    let (leading, trailing) = parsed.comments.borrow_all();
+    Self {
+      module: parsed.module.clone(),
+      original: parsed.original.clone(),
+      leading: leading.clone(),
+      trailing: trailing.clone(),
+      import: parsed.import.clone(),
+    }
+  }
+
+  fn restore(&self, source: &str, options: &Options) -> Parsed {
+    let cm = Rc::new(SourceMap::default());
+    cm.new_source_file(FileName::Custom(options.filename()), source.to_string());
+    let comments = SingleThreadedComments::from_leading_and_trailing(
+      Rc::new(RefCell::new(self.leading.clone())),
+      Rc::new(RefCell::new(self.trailing.clone())),
+    );
+    Parsed {
+      cm,
+      module: self.module.clone(),
+      original: self.original.clone(),
+      comments,
+      import: self.import.clone(),
+    }
+  }
+}
+
+/// A cached analysis, with the run it was last used in.
+struct Entry {
+  hash: u64,
+  analyzed: Arc<Analyzed>,
+  run: u64,
+}
+
+#[derive(Default)]
+struct Cache {
+  entries: HashMap<String, Entry>,
+  /// Number of runs so far.
+  runs: u64,
+}
+
+/// Removes exports from many modules with the same options, in parallel on
+/// native targets.
+///
+/// The analysis of every module is cached by filename and content hash, so
+/// running a batch again only parses the modules that changed. Only the
+/// analysis is cached, a changed module is parsed and resolved from scratch.
+/// Once more than `capacity` modules are cached, the ones left out of the
+/// most runs are dropped.
+pub struct Batch {
+  options: Options,
+  capacity: usize,
+  cache: Mutex<Cache>,
+}
+
+impl Batch {
+  /// Modules cached by default.
+  pub const DEFAULT_CAPACITY: usize = 1024;
+
+  pub fn new(options: Options) -> Self {
+    Self::with_capacity(options, Self::DEFAULT_CAPACITY)
+  }
+
+  /// A batch caching the analysis of at most `capacity` modules.
+  pub fn with_capacity(options: Options, capacity: usize) -> Self {
+    Self {
+      options,
+      capacity,
+      cache: Mutex::default(),
+    }
+  }
+
+  /// Transform `jobs`, returns their outputs in the same order.
+  pub fn run(&self, jobs: &[Job]) -> Vec<Result<Output, Error>> {
+    let run = {
+      let mut cache = self.cache.lock().unwrap();
+      cache.runs += 1;
+      cache.runs
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    let outputs = {
+      use rayon::prelude::*;
+      jobs.par_iter().map(|job| self.run_job(job, run)).collect()
+    };
+    #[cfg(target_arch = "wasm32")]
+    let outputs = jobs.iter().map(|job| self.run_job(job, run)).collect();
+    self.evict();
+    outputs
+  }
+
+  /// Number of modules with a cached analysis.
+  pub fn cached(&self) -> usize {
+    self.cache.lock().unwrap().entries.len()
+  }
+
+  /// Drop the least recently used analyses beyond the capacity.
+  fn evict(&self) {
+    let mut cache = self.cache.lock().unwrap();
+    let excess = cache.entries.len().saturating_sub(self.capacity);
+    if excess == 0 {
+      return;
+    }
+    let mut entries = (cache.entries.iter())
+      .map(|(filename, entry)| (entry.run, filename.clone()))
+      .collect::<Vec<_>>();
+    entries.sort();
+    for (_, filename) in entries.into_iter().take(excess) {
+      cache.entries.remove(&filename);
+    }
+  }
+
+  fn run_job(&self, job: &Job, run: u64) -> Result<Output, Error> {
+    if self.options.input_source_map.is_some() {
+      return Err(Error::Options {
+        message: "input_source_map must be set on each job of a batch".to_string(),
+      });
+    }
+    let options = Options {
+      filename: Some(job.filename.clone()),
+      ..self.options.clone()
+    };
+    let input_source_map = match &job.input_source_map {
+      Some(input) => Some(source_map::parse(input)?),
+      None => None,
+    };
+
+    let mut hasher = DefaultHasher::new();
+    job.source.hash(&mut hasher);
+    let hash = hasher.finish();
+
+    let cached = {
+      let mut cache = self.cache.lock().unwrap();
+      let entry = (cache.entries.get_mut(&job.filename)).filter(|x| x.hash == hash);
+      entry.map(|entry| {
+        entry.run = run;
+        entry.analyzed.clone()
+      })
+    };
+    let parsed = match cached {
+      Some(analyzed) => analyzed.restore(&job.source, &options),
+      None => {
+        let parsed = parse(&job.source, &options)?;
+        let analyzed = Arc::new(Analyzed::new(&parsed));
+        self.cache.lock().unwrap().entries.insert(
+          job.filename.clone(),
+          Entry {
+            hash,
+            analyzed,
+            run,
+          },
+        );
+        parsed
+      }
+    };
+
+    transform(
+      parsed,
+      job.exports.clone(),
+      &options,
+      input_source_map.as_ref(),
+    )
+  }
+}
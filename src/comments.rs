@@ -0,0 +1,104 @@
+//! Removing the comments of removed nodes, and the ones the
+//! [`CommentPolicy`] does not keep.
+
+use std::collections::HashSet;
+
+use swc_common::{
+  comments::{Comment, CommentKind, SingleThreadedComments},
+  BytePos, SourceMap, Span,
+};
+
+use crate::CommentPolicy;
+
+/// `/*! ... */`, or a comment containing `@license`.
+fn is_license(comment: &Comment) -> bool {
+  (comment.kind == CommentKind::Block && comment.text.starts_with('!'))
+    || comment.text.contains("@license")
+}
+
+/// Drop the comments attached to or inside the `removed` nodes, and the ones
+/// `policy` does not keep. Kept license comments of removed nodes are moved
+/// to `start`.
+///
+/// A comment after a removed node on the same line is attached to it, unless
+/// only whitespace separates it from the next of the `kept` module items,
+/// whose leading comment it becomes.
+///
+/// Returns the spans of the dropped comments.
+pub(crate) fn strip(
+  cm: &SourceMap,
+  comments: &SingleThreadedComments,
+  removed: &[Span],
+  kept: &[BytePos],
+  policy: CommentPolicy,
+  start: BytePos,
+) -> Vec<Span> {
+  let starts = removed.iter().map(|x| x.lo).collect::<HashSet<_>>();
+  let ends = removed.iter().map(|x| x.hi).collect::<HashSet<_>>();
+  let inside = |c: &Comment| {
+    removed
+      .iter()
+      .any(|x| x.lo <= c.span.lo && c.span.hi <= x.hi)
+  };
+  // `/** b */` of `export const a = 1; /** b */ export const b = 2;`
+  let leads = |c: &Comment| {
+    let next = kept.iter().find(|x| **x >= c.span.hi)?;
+    let between = Span::new(c.span.hi, *next, Default::default());
+    let blank = cm.with_snippet_of_span(between, |x| x.trim().is_empty());
+    blank.is_ok_and(|x| x).then_some(*next)
+  };
+
+  let mut dropped = vec![];
+  let mut moved = vec![];
+  let mut leading_moved = vec![];
+  let (mut leading, mut trailing) = comments.borrow_all_mut();
+  for (map, attached, is_trailing) in [
+    (&mut *leading, &starts, false),
+    (&mut *trailing, &ends, true),
+  ] {
+    map.retain(|pos, list| {
+      let attached = attached.contains(pos);
+      list.retain(|c| {
+        let keep = match policy {
+          CommentPolicy::All => true,
+          CommentPolicy::License => is_license(c),
+          CommentPolicy::None => false,
+        };
+        let next = match attached && is_trailing {
+          true => leads(c),
+          false => None,
+        };
+        match (keep, attached || inside(c)) {
+          (true, false) => true,
+          (true, true) if next.is_some() => {
+            leading_moved.extend(next.map(|x| (x, c.clone())));
+            false
+          }
+          (true, true) if is_license(c) => {
+            moved.push(c.clone());
+            false
+          }
+          _ => {
+            dropped.push(c.span);
+            false
+          }
+        }
+      });
+      !list.is_empty()
+    });
+  }
+  for (pos, comment) in leading_moved {
+    let list = leading.entry(pos).or_default();
+    let i = list.partition_point(|x| x.span.lo < comment.span.lo);
+    list.insert(i, comment);
+  }
+  if !moved.is_empty() {
+    moved.sort_by_key(|x| x.span.lo);
+    let list = leading.entry(start).or_default();
+    moved.append(list);
+    *list = moved;
+  }
+
+  dropped.sort_by_key(|x| x.lo);
+  dropped
+}
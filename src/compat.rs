@@ -0,0 +1,88 @@
+//! Lowering the transformed module to an older ECMAScript version.
+
+use swc_common::comments::SingleThreadedComments;
+use swc_ecma_transforms_proposal::{
+  decorator_2022_03::decorator_2022_03, explicit_resource_management::explicit_resource_management,
+};
+use swc_ecmascript::{
+  ast::{EsVersion, Module},
+  transforms::{
+    compat::{es2015, es2016, es2017, es2018, es2019, es2020, es2021, es2022, es3},
+    fixer,
+    helpers::{inject_helpers, Helpers, HELPERS},
+    hygiene,
+  },
+  visit::{FoldWith, VisitMutWith},
+};
+
+use crate::{resolve_again, Error, Options};
+
+/// Run swc's compat transforms for every version newer than `options.target`.
+pub(crate) fn downlevel(
+  comments: &SingleThreadedComments,
+  module: Module,
+  options: &Options,
+) -> Result<Module, Error> {
+  if options.language().is_typescript() {
+    return Err(Error::Options {
+      message: "downlevel does not support TypeScript".to_string(),
+    });
+  }
+
+  let target = options.target;
+  let module = resolve_again(module, false, |module, unresolved_mark, _| {
+    let mut module = HELPERS.set(&Helpers::new(false), || {
+      let mut module = module;
+      // decorators and `using` are not part of any edition yet, their
+      // output is lowered further below
+      if target < EsVersion::EsNext {
+        module = module.fold_with(&mut decorator_2022_03());
+        module = module.fold_with(&mut explicit_resource_management());
+      }
+      if target < EsVersion::Es2022 {
+        module = module.fold_with(&mut es2022(
+          Some(comments),
+          Default::default(),
+          unresolved_mark,
+        ));
+      }
+      if target < EsVersion::Es2021 {
+        module = module.fold_with(&mut es2021());
+      }
+      if target < EsVersion::Es2020 {
+        module = module.fold_with(&mut es2020(Default::default(), unresolved_mark));
+      }
+      if target < EsVersion::Es2019 {
+        module = module.fold_with(&mut es2019());
+      }
+      if target < EsVersion::Es2018 {
+        module = module.fold_with(&mut es2018(Default::default()));
+      }
+      if target < EsVersion::Es2017 {
+        module = module.fold_with(&mut es2017(
+          Default::default(),
+          Some(comments),
+          unresolved_mark,
+        ));
+      }
+      if target < EsVersion::Es2016 {
+        module = module.fold_with(&mut es2016());
+      }
+      if target < EsVersion::Es2015 {
+        module = module.fold_with(&mut es2015(
+          unresolved_mark,
+          Some(comments),
+          Default::default(),
+        ));
+      }
+      if target < EsVersion::Es5 {
+        module = module.fold_with(&mut es3(true));
+      }
+      module.fold_with(&mut inject_helpers(unresolved_mark))
+    });
+    module.visit_mut_with(&mut hygiene());
+    module.visit_mut_with(&mut fixer(Some(comments)));
+    module
+  });
+  Ok(module)
+}
@@ -0,0 +1,619 @@
+//! Splicing the transformed module into the original source, so that
+//! untouched code is kept byte for byte.
+
+use std::{collections::HashMap, rc::Rc};
+
+use serde::Serialize;
+use swc_common::{
+  comments::{Comments, SingleThreadedComments},
+  BytePos, EqIgnoreSpan, LineCol, SourceMap, Span, Spanned,
+};
+use swc_ecmascript::{
+  ast::{
+    Decl, ExportDecl, ImportDecl, ImportSpecifier, ModuleDecl, ModuleItem, NamedExport,
+    ObjectPatProp, Pat, Stmt, VarDecl, VarDeclarator,
+  },
+  codegen::{self, text_writer::JsWriter, Emitter, Node},
+};
+
+use crate::{error::Utf16Offsets, using::UsingWriter, Error};
+
+/// A replacement of a range of the original source.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Edit {
+  /// Byte offset of the start of the range.
+  #[serde(skip)]
+  pub start: usize,
+  /// Byte offset of the end of the range.
+  #[serde(skip)]
+  pub end: usize,
+  /// UTF-16 offset of the start of the range, the index in a JavaScript
+  /// string. Serialized as `start`.
+  #[serde(rename = "start")]
+  pub utf16_start: usize,
+  /// UTF-16 offset of the end of the range, serialized as `end`.
+  #[serde(rename = "end")]
+  pub utf16_end: usize,
+  /// Text replacing the range, empty if it is removed.
+  pub text: String,
+}
+
+/// A replacement of the byte range `start..end`, with the source map
+/// mappings of its text, relative to its start.
+struct Replacement {
+  start: usize,
+  end: usize,
+  text: String,
+  mappings: Vec<(BytePos, LineCol)>,
+}
+
+fn same_span(a: Span, b: Span) -> bool {
+  a.lo == b.lo && a.hi == b.hi
+}
+
+/// For each element of `orig`, the element of `new` with the same span.
+/// `None` if `new` has elements that are not in `orig`, or in another order.
+fn match_list<'a, T: Spanned>(orig: &[T], new: &'a [T]) -> Option<Vec<Option<&'a T>>> {
+  let mut kept = orig.iter().map(|_| None).collect::<Vec<_>>();
+  let mut cursor = 0;
+  for n in new {
+    let i = orig[cursor..]
+      .iter()
+      .position(|o| same_span(o.span(), n.span()))?;
+    kept[cursor + i] = Some(n);
+    cursor += i + 1;
+  }
+  Some(kept)
+}
+
+/// Collects the edits turning the original module into the transformed one.
+pub(crate) struct Diff<'a> {
+  cm: Rc<SourceMap>,
+  source: &'a str,
+  start_pos: BytePos,
+  cfg: codegen::Config,
+  comments: &'a SingleThreadedComments,
+  /// Comments removed from the module, sorted.
+  removed_comments: Vec<Span>,
+  /// `using` declarations printed as `const` ones.
+  using: &'a HashMap<BytePos, bool>,
+  replacements: Vec<Replacement>,
+}
+
+impl<'a> Diff<'a> {
+  pub fn new(
+    cm: Rc<SourceMap>,
+    source: &'a str,
+    start_pos: BytePos,
+    cfg: codegen::Config,
+    comments: &'a SingleThreadedComments,
+    removed_comments: Vec<Span>,
+    using: &'a HashMap<BytePos, bool>,
+  ) -> Self {
+    Self {
+      cm,
+      source,
+      start_pos,
+      cfg,
+      comments,
+      removed_comments,
+      using,
+      replacements: vec![],
+    }
+  }
+
+  fn offset(&self, pos: BytePos) -> usize {
+    (pos - self.start_pos).0 as usize
+  }
+
+  fn push(&mut self, start: usize, end: usize, text: String, mappings: Vec<(BytePos, LineCol)>) {
+    self.replacements.push(Replacement {
+      start,
+      end,
+      text,
+      mappings,
+    });
+  }
+
+  fn remove(&mut self, lo: BytePos, hi: BytePos) {
+    self.push(self.offset(lo), self.offset(hi), String::new(), vec![]);
+  }
+
+  /// Extend `start..end` to the lines it is alone on.
+  fn whole_lines(&self, start: usize, end: usize) -> (usize, usize) {
+    let line_start = self.source[..start].rfind('\n').map_or(0, |x| x + 1);
+    let line_end = match self.source[end..].find('\n') {
+      Some(x) => end + x + 1,
+      None => self.source.len(),
+    };
+    match self.source[line_start..start].trim().is_empty()
+      && self.source[end..line_end].trim().is_empty()
+    {
+      true => (line_start, line_end),
+      false => (start, end),
+    }
+  }
+
+  /// Remove a module item and its removed comments, together with the lines
+  /// they are alone on.
+  fn remove_lines(&mut self, span: Span) {
+    let (mut start, mut end) = (self.offset(span.lo), self.offset(span.hi));
+    // only the comments right next to it, up to the previous edit
+    let floor = (self.replacements.iter())
+      .map(|x| x.end)
+      .filter(|x| *x <= start)
+      .max()
+      .unwrap_or(0);
+    for comment in self.removed_comments.iter().rev() {
+      let (lo, hi) = (self.offset(comment.lo), self.offset(comment.hi));
+      if hi > start {
+        continue;
+      }
+      if lo < floor || !self.source[hi..start].trim().is_empty() {
+        break;
+      }
+      start = lo;
+    }
+    for comment in &self.removed_comments {
+      let (lo, hi) = (self.offset(comment.lo), self.offset(comment.hi));
+      if lo < end {
+        continue;
+      }
+      if !self.source[end..lo].trim().is_empty() || self.source[end..lo].contains('\n') {
+        break;
+      }
+      end = hi;
+    }
+
+    let (lines_start, lines_end) = self.whole_lines(start, end);
+    if (lines_start, lines_end) != (start, end) {
+      (start, end) = (lines_start, lines_end);
+      // and a blank line after them, if there is one before them
+      let blank_before = match start {
+        0 => true,
+        _ => (self.source[..start - 1].rsplit('\n').next()).is_some_and(|x| x.trim().is_empty()),
+      };
+      let blank_after = self.source[end..].find('\n').map(|x| end + x + 1);
+      if let Some(x) =
+        blank_after.filter(|x| blank_before && self.source[end..*x].trim().is_empty())
+      {
+        end = x;
+      }
+    } else {
+      // and the spaces separating it from the rest of its line
+      let rest = &self.source[end..];
+      end += rest.len() - rest.trim_start_matches([' ', '\t']).len();
+    }
+    self.push(start, end, String::new(), vec![]);
+  }
+
+  /// Print `nodes` in place of `span`.
+  fn print<N: Node>(&mut self, span: Span, nodes: &[&N]) -> Result<(), Error> {
+    // they are outside of `span`, and kept in the source
+    let leading = self.comments.take_leading(span.lo);
+    let trailing = self.comments.take_trailing(span.hi);
+    let mut buf = vec![];
+    let mut mappings = vec![];
+    let result = {
+      let mut emitter = Emitter {
+        cfg: self.cfg,
+        cm: self.cm.clone(),
+        comments: Some(self.comments),
+        wr: UsingWriter::new(
+          JsWriter::new(self.cm.clone(), "\n", &mut buf, Some(&mut mappings)),
+          self.using,
+        ),
+      };
+      nodes
+        .iter()
+        .try_for_each(|node| node.emit_with(&mut emitter))
+    };
+    // the module may still be printed whole
+    if let Some(leading) = leading {
+      self.comments.add_leading_comments(span.lo, leading);
+    }
+    if let Some(trailing) = trailing {
+      self.comments.add_trailing_comments(span.hi, trailing);
+    }
+    result.map_err(|err| Error::Codegen {
+      message: err.to_string(),
+    })?;
+    let text = String::from_utf8_lossy(&buf).trim_end().to_string();
+    self.push(self.offset(span.lo), self.offset(span.hi), text, mappings);
+    Ok(())
+  }
+
+  /// Remove the elements of `orig` that are not `kept`, with the commas
+  /// separating them from the kept ones.
+  fn remove_unmatched<T: Spanned, U>(&mut self, orig: &[T], kept: &[Option<U>]) {
+    let mut i = 0;
+    while i < orig.len() {
+      if kept[i].is_some() {
+        i += 1;
+        continue;
+      }
+      let mut j = i;
+      while kept.get(j + 1).is_some_and(|x| x.is_none()) {
+        j += 1;
+      }
+      match orig.get(j + 1) {
+        // `foo, ` of `foo, bar`
+        Some(next) => self.remove(orig[i].span().lo, next.span().lo),
+        // `, bar` of `foo, bar`
+        None if i > 0 => self.remove(orig[i - 1].span().hi, orig[j].span().hi),
+        None => self.remove(orig[i].span().lo, orig[j].span().hi),
+      }
+      i = j + 1;
+    }
+  }
+
+  /// Remove the elements of `orig` missing from `new`, which must be
+  /// unchanged otherwise.
+  fn list<T: Spanned + EqIgnoreSpan>(&mut self, orig: &[T], new: &[T]) -> bool {
+    let Some(kept) = match_list(orig, new) else {
+      return false;
+    };
+    let changed = orig
+      .iter()
+      .zip(&kept)
+      .any(|(o, n)| n.is_some_and(|n| !o.eq_ignore_span(n)));
+    if new.is_empty() || changed {
+      return false;
+    }
+    self.remove_unmatched(orig, &kept);
+    true
+  }
+
+  pub fn module_items(&mut self, orig: &[ModuleItem], new: &[ModuleItem]) -> Result<(), Error> {
+    // new items by the original item they replace, items without a place in
+    // the source follow the one before them
+    let mut groups: Vec<(usize, Vec<&ModuleItem>)> = vec![];
+    let mut leading = vec![];
+    let mut cursor = 0;
+    for item in new {
+      let span = item.span();
+      let found = match span.is_dummy() {
+        true => None,
+        false => orig[cursor..].iter().position(|x| {
+          let orig = x.span();
+          orig.lo <= span.lo && span.hi <= orig.hi
+        }),
+      };
+      match (found, groups.last_mut()) {
+        (Some(i), _) => {
+          groups.push((cursor + i, vec![item]));
+          cursor += i + 1;
+        }
+        (None, Some((_, items))) => items.push(item),
+        (None, None) => leading.push(item),
+      }
+    }
+    if !leading.is_empty() {
+      match groups.first_mut() {
+        Some((_, items)) => {
+          leading.append(items);
+          *items = leading;
+        }
+        None => {
+          let end = self.start_pos + BytePos(self.source.len() as u32);
+          self.print(Span::new(end, end, Default::default()), &leading)?;
+        }
+      }
+    }
+
+    let mut groups = groups.into_iter().peekable();
+    for (i, orig) in orig.iter().enumerate() {
+      let Some((_, items)) = groups.next_if(|(x, _)| *x == i) else {
+        self.remove_lines(orig.span());
+        continue;
+      };
+      match items[..] {
+        [new] if same_span(orig.span(), new.span()) => self.module_item(orig, new)?,
+        _ => self.print(orig.span(), &items)?,
+      }
+    }
+    Ok(())
+  }
+
+  fn module_item(&mut self, orig: &ModuleItem, new: &ModuleItem) -> Result<(), Error> {
+    if orig.eq_ignore_span(new) {
+      return Ok(());
+    }
+    let done = match (orig, new) {
+      (ModuleItem::Stmt(Stmt::Decl(Decl::Var(o))), ModuleItem::Stmt(Stmt::Decl(Decl::Var(n))))
+      | (
+        ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl {
+          decl: Decl::Var(o), ..
+        })),
+        ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl {
+          decl: Decl::Var(n), ..
+        })),
+      ) => self.var_decl(o, n)?,
+      (
+        ModuleItem::ModuleDecl(ModuleDecl::Import(o)),
+        ModuleItem::ModuleDecl(ModuleDecl::Import(n)),
+      ) => self.import(o, n),
+      (
+        ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(o)),
+        ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(n)),
+      ) => self.named_export(o, n),
+      _ => false,
+    };
+    match done {
+      true => Ok(()),
+      false => self.print(orig.span(), &[new]),
+    }
+  }
+
+  fn var_decl(&mut self, orig: &VarDecl, new: &VarDecl) -> Result<bool, Error> {
+    if orig.kind != new.kind || orig.declare != new.declare {
+      return Ok(false);
+    }
+    self.declarators(&orig.decls, &new.decls)
+  }
+
+  fn declarators(&mut self, orig: &[VarDeclarator], new: &[VarDeclarator]) -> Result<bool, Error> {
+    if new.is_empty() {
+      return Ok(false);
+    }
+    let Some(kept) = match_list(orig, new) else {
+      return Ok(false);
+    };
+    self.remove_unmatched(orig, &kept);
+    for (o, n) in orig.iter().zip(kept) {
+      if let Some(n) = n {
+        self.var_declarator(o, n)?;
+      }
+    }
+    Ok(true)
+  }
+
+  fn var_declarator(&mut self, orig: &VarDeclarator, new: &VarDeclarator) -> Result<(), Error> {
+    if orig.eq_ignore_span(new) {
+      return Ok(());
+    }
+    if orig.init.is_some() != new.init.is_some() {
+      return self.print(orig.span, &[new]);
+    }
+    self.pat(&orig.name, &new.name)?;
+    match (&orig.init, &new.init) {
+      (Some(o), Some(n)) if !o.eq_ignore_span(n) => self.print(o.span(), &[n]),
+      _ => Ok(()),
+    }
+  }
+
+  fn pat(&mut self, orig: &Pat, new: &Pat) -> Result<(), Error> {
+    if orig.eq_ignore_span(new) {
+      return Ok(());
+    }
+    match (orig, new) {
+      // `[foo, bar]` => `[, bar]`
+      (Pat::Array(o), Pat::Array(n))
+        if o.elems.len() == n.elems.len()
+          && o.optional == n.optional
+          && o.type_ann.eq_ignore_span(&n.type_ann)
+          && (o.elems.iter().zip(&n.elems)).all(|(o, n)| o.is_some() || n.is_none()) =>
+      {
+        for (o, n) in o.elems.iter().zip(&n.elems) {
+          match (o, n) {
+            (Some(o), Some(n)) => self.pat(o, n)?,
+            (Some(o), None) => self.remove(o.span().lo, o.span().hi),
+            (None, _) => {}
+          }
+        }
+        Ok(())
+      }
+      // `{ foo, bar }` => `{ bar }`
+      (Pat::Object(o), Pat::Object(n))
+        if o.optional == n.optional && o.type_ann.eq_ignore_span(&n.type_ann) =>
+      {
+        let kept = match match_list(&o.props, &n.props) {
+          Some(kept) if !n.props.is_empty() => kept,
+          _ => return self.print(orig.span(), &[new]),
+        };
+        self.remove_unmatched(&o.props, &kept);
+        for (o, n) in o.props.iter().zip(kept) {
+          if let Some(n) = n {
+            self.object_pat_prop(o, n)?;
+          }
+        }
+        Ok(())
+      }
+      // `[foo = 1]`
+      (Pat::Assign(o), Pat::Assign(n)) if o.right.eq_ignore_span(&n.right) => {
+        self.pat(&o.left, &n.left)
+      }
+      _ => self.print(orig.span(), &[new]),
+    }
+  }
+
+  fn object_pat_prop(&mut self, orig: &ObjectPatProp, new: &ObjectPatProp) -> Result<(), Error> {
+    if orig.eq_ignore_span(new) {
+      return Ok(());
+    }
+    match (orig, new) {
+      // `{ key: value }`
+      (ObjectPatProp::KeyValue(o), ObjectPatProp::KeyValue(n)) if o.key.eq_ignore_span(&n.key) => {
+        self.pat(&o.value, &n.value)
+      }
+      _ => self.print(orig.span(), &[new]),
+    }
+  }
+
+  fn import(&mut self, orig: &ImportDecl, new: &ImportDecl) -> bool {
+    // `foo, { bar }` or `foo, * as bar`, removing the default or namespace
+    // import, or every named import, changes the braces around them
+    let named = |x: &ImportDecl| {
+      x.specifiers
+        .iter()
+        .take_while(|x| !matches!(x, ImportSpecifier::Named(_)))
+        .count()
+    };
+    let (o, n) = (named(orig), named(new));
+    if !orig.src.eq_ignore_span(&new.src)
+      || orig.type_only != new.type_only
+      || !orig.with.eq_ignore_span(&new.with)
+      || !orig.specifiers[..o].eq_ignore_span(&new.specifiers[..n])
+    {
+      return false;
+    }
+    self.list(&orig.specifiers[o..], &new.specifiers[n..])
+  }
+
+  fn named_export(&mut self, orig: &NamedExport, new: &NamedExport) -> bool {
+    if !orig.src.eq_ignore_span(&new.src)
+      || orig.type_only != new.type_only
+      || !orig.with.eq_ignore_span(&new.with)
+    {
+      return false;
+    }
+    self.list(&orig.specifiers, &new.specifiers)
+  }
+
+  /// Remove the removed comments outside of the other edits, runs of them
+  /// at once.
+  fn remove_comments(&mut self) {
+    let edits = self
+      .replacements
+      .iter()
+      .map(|x| (x.start, x.end))
+      .collect::<Vec<_>>();
+    let overlaps = |start: usize, end: usize| edits.iter().any(|(x, y)| start < *y && *x < end);
+
+    let mut runs: Vec<(usize, usize)> = vec![];
+    for comment in &self.removed_comments {
+      let (lo, hi) = (self.offset(comment.lo), self.offset(comment.hi));
+      if overlaps(lo, hi) {
+        continue;
+      }
+      match runs.last_mut() {
+        Some((_, end)) if self.source[*end..lo].trim().is_empty() => *end = hi,
+        _ => runs.push((lo, hi)),
+      }
+    }
+    for (start, end) in runs {
+      let (start, end) = self.whole_lines(start, end);
+      if !overlaps(start, end) {
+        self.push(start, end, String::new(), vec![]);
+      }
+    }
+  }
+
+  /// Apply the edits to the source, collecting the source map mappings of the
+  /// output if `mappings` is given.
+  ///
+  /// Overlapping removals are merged. `None` if a printed node overlaps
+  /// another edit, the module has to be printed whole then.
+  pub fn finish(
+    mut self,
+    mappings: Option<&mut Vec<(BytePos, LineCol)>>,
+  ) -> Option<(String, Vec<Edit>)> {
+    self.remove_comments();
+    self.replacements.sort_by_key(|x| x.start);
+    let mut replacements: Vec<Replacement> = vec![];
+    for replacement in self.replacements {
+      match replacements.last_mut() {
+        Some(last) if replacement.start < last.end => {
+          if !last.text.is_empty() || !replacement.text.is_empty() {
+            return None;
+          }
+          last.end = last.end.max(replacement.end);
+        }
+        _ => replacements.push(replacement),
+      }
+    }
+
+    let mut writer = Writer {
+      code: String::with_capacity(self.source.len()),
+      line: 0,
+      col: 0,
+      mappings,
+    };
+    let mut edits = vec![];
+    let mut cursor = 0;
+    let mut utf16 = Utf16Offsets::new(self.source);
+    for Replacement {
+      start,
+      end,
+      text,
+      mappings,
+    } in replacements
+    {
+      writer.copy(self.source, self.start_pos, cursor, start);
+      writer.insert(&text, &mappings);
+      cursor = end;
+      edits.push(Edit {
+        start,
+        end,
+        utf16_start: utf16.get(start),
+        utf16_end: utf16.get(end),
+        text,
+      });
+    }
+    writer.copy(self.source, self.start_pos, cursor, self.source.len());
+    Some((writer.code, edits))
+  }
+}
+
+/// Output of [`Diff::finish`], with its position.
+struct Writer<'a> {
+  code: String,
+  line: u32,
+  col: u32,
+  mappings: Option<&'a mut Vec<(BytePos, LineCol)>>,
+}
+
+impl Writer<'_> {
+  fn advance(&mut self, c: char) {
+    match c {
+      '\n' => {
+        self.line += 1;
+        self.col = 0;
+      }
+      c => self.col += c.len_utf16() as u32,
+    }
+  }
+
+  /// Copy a range of the source, mapping the start of every word.
+  fn copy(&mut self, source: &str, start_pos: BytePos, start: usize, end: usize) {
+    let mut space = true;
+    for (i, c) in source[start..end].char_indices() {
+      if space && !c.is_whitespace() {
+        if let Some(mappings) = &mut self.mappings {
+          let pos = start_pos + BytePos((start + i) as u32);
+          mappings.push((
+            pos,
+            LineCol {
+              line: self.line,
+              col: self.col,
+            },
+          ));
+        }
+      }
+      space = c.is_whitespace();
+      self.code.push(c);
+      self.advance(c);
+    }
+  }
+
+  /// Insert printed text, with its mappings.
+  fn insert(&mut self, text: &str, mappings: &[(BytePos, LineCol)]) {
+    if let Some(output) = &mut self.mappings {
+      output.extend(mappings.iter().map(|(pos, lc)| {
+        let col = match lc.line {
+          0 => self.col + lc.col,
+          _ => lc.col,
+        };
+        (
+          *pos,
+          LineCol {
+            line: self.line + lc.line,
+            col,
+          },
+        )
+      }));
+    }
+    self.code.push_str(text);
+    text.chars().for_each(|c| self.advance(c));
+  }
+}
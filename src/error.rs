@@ -0,0 +1,143 @@
+use std::fmt::{self, Display};
+
+use swc_common::{SourceFile, SourceMap, Span};
+
+/// Converts increasing byte offsets into a source to UTF-16 offsets, the
+/// indices in a JavaScript string.
+pub(crate) struct Utf16Offsets<'a> {
+  source: &'a str,
+  byte: usize,
+  utf16: usize,
+}
+
+impl<'a> Utf16Offsets<'a> {
+  pub fn new(source: &'a str) -> Self {
+    Self {
+      source,
+      byte: 0,
+      utf16: 0,
+    }
+  }
+
+  pub fn get(&mut self, byte: usize) -> usize {
+    if byte < self.byte {
+      *self = Self::new(self.source);
+    }
+    self.utf16 += self.source[self.byte..byte].encode_utf16().count();
+    self.byte = byte;
+    self.utf16
+  }
+}
+
+/// Where an error happened in the input source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Location {
+  /// Byte offset of the start of the span.
+  pub start: usize,
+  /// Byte offset of the end of the span.
+  pub end: usize,
+  /// UTF-16 offset of the start of the span, the index in a JavaScript
+  /// string.
+  pub utf16_start: usize,
+  /// UTF-16 offset of the end of the span.
+  pub utf16_end: usize,
+  /// 1-based line number.
+  pub line: usize,
+  /// 0-based column number, in characters.
+  pub column: usize,
+}
+
+impl Location {
+  pub(crate) fn new(cm: &SourceMap, fm: &SourceFile, span: Span) -> Self {
+    let loc = cm.lookup_char_pos(span.lo);
+    let (start, end) = (
+      (span.lo - fm.start_pos).0 as usize,
+      (span.hi - fm.start_pos).0 as usize,
+    );
+    let mut utf16 = Utf16Offsets::new(&fm.src);
+    Self {
+      start,
+      end,
+      utf16_start: utf16.get(start),
+      utf16_end: utf16.get(end),
+      line: loc.line,
+      column: loc.col.0,
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+  /// The source is not valid for the selected language.
+  Parse { message: String, location: Location },
+  /// The source is valid, but uses syntax this crate cannot handle.
+  Unsupported { message: String, location: Location },
+  /// Failed to print the transformed module.
+  Codegen { message: String },
+  /// The input source map is invalid, or the output one cannot be built.
+  SourceMap { message: String },
+  /// An export name pattern is neither a valid glob nor a valid regex.
+  Pattern { pattern: String, message: String },
+  /// The replacement of a defined expression is not a valid expression.
+  Define { name: String, message: String },
+  /// A module could not be resolved or loaded.
+  Load { id: String, message: String },
+  /// The options cannot be applied to the module.
+  Options { message: String },
+}
+
+impl Error {
+  /// Stable machine-readable error code.
+  pub fn code(&self) -> &'static str {
+    match self {
+      Error::Parse { .. } => "PARSE_ERROR",
+      Error::Unsupported { .. } => "UNSUPPORTED_SYNTAX",
+      Error::Codegen { .. } => "CODEGEN_ERROR",
+      Error::SourceMap { .. } => "SOURCE_MAP_ERROR",
+      Error::Pattern { .. } => "INVALID_PATTERN",
+      Error::Define { .. } => "INVALID_DEFINE",
+      Error::Load { .. } => "LOAD_ERROR",
+      Error::Options { .. } => "INVALID_OPTIONS",
+    }
+  }
+
+  pub fn message(&self) -> &str {
+    match self {
+      Error::Parse { message, .. } => message,
+      Error::Unsupported { message, .. } => message,
+      Error::Codegen { message } => message,
+      Error::SourceMap { message } => message,
+      Error::Pattern { message, .. } => message,
+      Error::Define { message, .. } => message,
+      Error::Load { message, .. } => message,
+      Error::Options { message } => message,
+    }
+  }
+
+  pub fn location(&self) -> Option<&Location> {
+    match self {
+      Error::Parse { location, .. } => Some(location),
+      Error::Unsupported { location, .. } => Some(location),
+      Error::Codegen { .. }
+      | Error::SourceMap { .. }
+      | Error::Pattern { .. }
+      | Error::Define { .. }
+      | Error::Load { .. }
+      | Error::Options { .. } => None,
+    }
+  }
+}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match (self, self.location()) {
+      (Error::Pattern { pattern, message }, _) => write!(f, "{}: {}", pattern, message),
+      (Error::Define { name, message }, _) => write!(f, "{}: {}", name, message),
+      (Error::Load { id, message }, _) => write!(f, "{}: {}", id, message),
+      (_, Some(loc)) => write!(f, "{} ({}:{})", self.message(), loc.line, loc.column),
+      (_, None) => write!(f, "{}", self.message()),
+    }
+  }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,153 @@
+//! Removing exports across re-exporting modules.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::{
+  matcher::Matcher, parse, source_map, transform, Analysis, Error, ExportKind, Mode, Options,
+  Output,
+};
+
+/// Resolves and loads the modules re-exported by another one.
+pub trait Loader {
+  /// Resolve `specifier` imported by the module `importer` to a module id,
+  /// `None` if it should not be followed, e.g. for packages.
+  fn resolve(&self, specifier: &str, importer: &str) -> Result<Option<String>, String>;
+
+  /// Source code of the module `id`.
+  fn load(&self, id: &str) -> Result<String, String>;
+}
+
+/// Remove `exports` from the module `entry`, and from the modules it
+/// re-exports them from.
+///
+/// `export { foo } from "./foo"` and `export * from "./foo"` are followed
+/// until the module defining `foo`, which is transformed as well. Returns
+/// the transformed modules by id, `entry` and those something was removed
+/// from.
+///
+/// `options` apply to every module, `mode`, `patterns`, `syntax` and
+/// `input_source_map` only to `entry`. In keep mode, `export * from "./foo"`
+/// in `entry` is kept and only the exports of `./foo` that are not kept are
+/// removed.
+pub fn remove_exports_from_graph(
+  entry: &str,
+  exports: Vec<String>,
+  options: &Options,
+  loader: &impl Loader,
+) -> Result<BTreeMap<String, Output>, Error> {
+  let load_error = |id: &str, message: String| Error::Load {
+    id: id.to_string(),
+    message,
+  };
+
+  let input_source_map = match &options.input_source_map {
+    Some(input) => Some(source_map::parse(input)?),
+    None => None,
+  };
+
+  // the kept exports, for the entry and the modules it re-exports everything
+  // from in keep mode
+  let keep = match options.mode {
+    Mode::Remove => None,
+    Mode::Keep => Some(Matcher::new(exports.clone(), options.patterns.clone())?),
+  };
+  let mut keeping = BTreeSet::new();
+  if keep.is_some() {
+    keeping.insert(entry.to_string());
+  }
+
+  let mut names = BTreeMap::<String, BTreeSet<String>>::new();
+  let mut outputs = BTreeMap::new();
+  let mut queue = VecDeque::from([entry.to_string()]);
+  let removes = match keep {
+    Some(_) => BTreeSet::new(),
+    None => exports.into_iter().collect(),
+  };
+  names.insert(entry.to_string(), removes);
+
+  while let Some(id) = queue.pop_front() {
+    let source = loader.load(&id).map_err(|err| load_error(&id, err))?;
+    // the language is inferred from the id
+    let options = Options {
+      mode: Mode::Remove,
+      patterns: vec![],
+      filename: Some(id.clone()),
+      syntax: options.syntax.filter(|_| id == entry),
+      input_source_map: None,
+      ..options.clone()
+    };
+    let parsed = parse(&source, &options)?;
+    let analysis = Analysis::new(&parsed.import);
+    let mut removes = names[&id].clone();
+    let keep = keep.as_ref().filter(|_| keeping.contains(&id));
+    if let Some(keep) = keep {
+      // `export *` does not re-export the default export
+      let kept = |name: &str| keep.is_match(name) || (id != entry && name == "default");
+      removes.extend(
+        (analysis.exports.iter())
+          .filter(|x| x.kind != ExportKind::Star && !kept(&x.name))
+          .map(|x| x.name.clone()),
+      );
+    }
+    let input_source_map = input_source_map.as_ref().filter(|_| id == entry);
+    let mut output = transform(
+      parsed,
+      removes.into_iter().collect(),
+      &options,
+      input_source_map,
+    )?;
+
+    let mut follow = vec![];
+    for export in &analysis.exports {
+      let (Some(src), Some(import)) = (&export.source, export.imports.first()) else {
+        continue;
+      };
+      match export.kind {
+        // export { foo as bar } from "./foo";
+        ExportKind::ReExport
+          if import.imported != "*" && output.report.removed_exports.contains(&export.name) =>
+        {
+          follow.push((src, Some(import.imported.clone())));
+        }
+        // export * from "./foo";
+        ExportKind::Star => {
+          if keep.is_some() {
+            follow.push((src, None));
+          }
+          for name in &output.report.not_found {
+            if names[&id].contains(name) && name != "default" {
+              follow.push((src, Some(name.clone())));
+            }
+          }
+        }
+        _ => {}
+      }
+    }
+
+    for (src, name) in follow {
+      let target = loader
+        .resolve(src, &id)
+        .map_err(|err| load_error(src, err))?;
+      if let Some(target) = target {
+        let names = names.entry(target.clone()).or_default();
+        let changed = match name {
+          Some(name) => names.insert(name),
+          None => keeping.insert(target.clone()),
+        };
+        if changed && !queue.contains(&target) {
+          queue.push_back(target);
+        }
+      }
+    }
+
+    if let Some(keep) = keep.filter(|_| id == entry) {
+      let exports = analysis.exports.iter().map(|x| &x.name);
+      output.report.not_found = keep.unmatched(exports).into_iter().collect();
+    }
+
+    outputs.insert(id, output);
+  }
+
+  outputs.retain(|id, output| id == entry || !output.report.removed_exports.is_empty());
+  Ok(outputs)
+}
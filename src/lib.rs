@@ -1,69 +1,400 @@
-use std::rc::Rc;
+use std::{
+  collections::{BTreeMap, HashMap},
+  rc::Rc,
+};
 
+mod analysis;
+mod batch;
+mod comments;
+mod compat;
+mod edit;
+mod error;
+mod graph;
+mod matcher;
+mod minify;
+#[cfg(feature = "node")]
+// napi only registers the bindings outside of tests
+#[cfg_attr(test, allow(dead_code))]
+mod node;
+mod options;
+mod source_map;
 #[cfg(test)]
 mod test;
+mod using;
 mod visitor;
+mod wasm;
 
+pub use analysis::{Analysis, Declaration, Export, ExportKind, Import};
+pub use batch::{Batch, Job};
+use edit::Diff;
+pub use edit::Edit;
+pub use error::{Error, Location};
+pub use graph::{remove_exports_from_graph, Loader};
+use matcher::Matcher;
+pub use options::{CommentPolicy, Language, Options};
+use serde::Serialize;
 use swc_common::{
-  comments::SingleThreadedComments, input::SourceFileInput, FileName, Mark, SourceMap,
+  comments::SingleThreadedComments, input::SourceFileInput, FileName, Mark, SourceMap, Spanned,
+  SyntaxContext,
 };
 use swc_common::{Globals, GLOBALS};
+use swc_ecmascript::ast::{Expr, Module, ModuleItem};
 use swc_ecmascript::transforms::resolver;
 use swc_ecmascript::{
-  ast::EsVersion,
-  codegen::{text_writer::JsWriter, Emitter},
-  parser::{lexer::Lexer, EsConfig, Parser, Syntax},
+  codegen::{self, text_writer::JsWriter, Emitter},
+  parser::{lexer::Lexer, Parser, Syntax},
   visit::{VisitMutWith, VisitWith},
 };
-use visitor::{ImportVisitor, RemoveVisitor};
-use wasm_bindgen::prelude::*;
+use using::UsingWriter;
+pub use visitor::Mode;
+use visitor::{
+  Annotations, DefineVisitor, DropSpan, IdentCounter, ImportVisitor, RemoveVisitor, ResetContext,
+};
 
-#[wasm_bindgen]
+/// Remove `exports` from a JavaScript module.
+///
+/// # Panics
+///
+/// Panics if the source cannot be transformed, see
+/// [`remove_exports_with_options`] for a fallible version.
 pub fn remove_exports(source: &str, exports: Vec<String>) -> String {
+  match remove_exports_with_options(source, exports, &Options::default()) {
+    Ok(output) => output.code,
+    Err(err) => panic!("{}", err),
+  }
+}
+
+/// Transformed code with its source map.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Output {
+  pub code: String,
+  /// v3 source map as JSON, if `source_maps` is enabled.
+  pub map: Option<String>,
+  /// What was removed from the module.
+  pub report: Report,
+  /// Edits turning the source into `code`, if `preserve_format` is enabled
+  /// and they could be applied without printing the whole module.
+  pub edits: Option<Vec<Edit>>,
+}
+
+/// What was removed from a module.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Report {
+  /// Export names that were removed.
+  pub removed_exports: Vec<String>,
+  /// Module-level declarations that were removed, including the removed
+  /// exports and the ones only they used.
+  pub removed_decls: Vec<String>,
+  /// Import specifiers that were removed, by source module. Default and
+  /// namespace imports are reported as `default` and `*`.
+  pub removed_imports: BTreeMap<String, Vec<String>>,
+  /// Requested export names that were not found in the module.
+  pub not_found: Vec<String>,
+}
+
+impl Report {
+  fn new(remove: RemoveVisitor) -> Self {
+    Self {
+      removed_exports: remove.removed_exports.into_iter().collect(),
+      removed_decls: remove.removed_decls.into_iter().collect(),
+      removed_imports: remove
+        .removed_imports
+        .into_iter()
+        .map(|(src, names)| (src, names.into_iter().collect()))
+        .collect(),
+      not_found: remove.not_found.into_iter().collect(),
+    }
+  }
+}
+
+/// A parsed and resolved module, with its module-level bindings analyzed.
+struct Parsed {
+  cm: Rc<SourceMap>,
+  module: Module,
+  /// The module as parsed, if `preserve_format` is enabled.
+  original: Option<Module>,
+  comments: SingleThreadedComments,
+  import: ImportVisitor,
+}
+
+fn parse(source: &str, options: &Options) -> Result<Parsed, Error> {
+  let language = options.language();
   let cm = Rc::new(SourceMap::default());
-  let fm = cm.new_source_file(FileName::Custom("input.js".to_string()), source.to_string());
+  let fm = cm.new_source_file(FileName::Custom(options.filename()), source.to_string());
 
   let comments = SingleThreadedComments::default();
   let lexer = Lexer::new(
-    Syntax::Es(EsConfig::default()),
-    EsVersion::Es2022,
+    language.syntax(),
+    options.input_target,
     SourceFileInput::from(&*fm),
     Some(&comments),
   );
 
   let mut parser = Parser::new_from(lexer);
-  let mut module = parser.parse_module().unwrap();
+  let parsed = match options.commonjs {
+    true => parser.parse_script().map(|script| Module {
+      span: script.span,
+      body: script.body.into_iter().map(ModuleItem::Stmt).collect(),
+      shebang: script.shebang,
+    }),
+    false => parser.parse_module(),
+  };
+  let mut module = match (parsed, parser.take_errors().into_iter().next()) {
+    (Ok(module), None) => module,
+    (Err(err), _) | (Ok(_), Some(err)) => {
+      return Err(Error::Parse {
+        message: err.kind().msg().to_string(),
+        location: Location::new(&cm, &fm, err.span()),
+      })
+    }
+  };
+
+  module.visit_mut_with(&mut using::MergeSemicolon);
+
+  let mut defines = HashMap::new();
+  for (name, value) in &options.define {
+    defines.insert(name.clone(), parse_define(&cm, name, value)?);
+  }
 
+  let mut dropped = vec![];
+  let mut original = None;
+  let mut unresolved = SyntaxContext::empty();
   let globals = Globals::new();
   GLOBALS.set(&globals, || {
-    let mut resolver = resolver(Mark::new(), Mark::new(), false);
+    let unresolved_mark = Mark::new();
+    unresolved = unresolved.apply_mark(unresolved_mark);
+    let mut resolver = resolver(unresolved_mark, Mark::new(), language.is_typescript());
     module.visit_mut_with(&mut resolver);
-  });
+    original = options.preserve_format.then(|| module.clone());
 
-  // println!("=============");
-  // println!("before = {:?}", module);
+    if !defines.is_empty() {
+      let before = IdentCounter::count(&module);
+      module.visit_mut_with(&mut DefineVisitor {
+        defines,
+        unresolved,
+      });
+      // bindings only used in removed branches
+      let after = IdentCounter::count(&module);
+      dropped.extend(
+        before
+          .counts
+          .into_iter()
+          .filter(|(id, count)| *count > 1 && after.counts.get(id).is_some_and(|x| *x <= 1))
+          .map(|(id, _)| id),
+      );
+    }
+  });
 
-  let mut import = ImportVisitor::default();
+  let mut import = ImportVisitor {
+    annotations: Annotations::new(&comments),
+    commonjs: options.commonjs,
+    unresolved,
+    prune_statements: options.prune_statements,
+    drop_disposables: options.drop_disposables,
+    ..Default::default()
+  };
   module.visit_with(&mut import);
-  println!("import = {:?}", import);
+  // they are removed if nothing else uses them, unless declaring them has
+  // side effects
+  import.pure_decls.extend(
+    dropped
+      .into_iter()
+      .filter(|id| import.side_effect_free_decls.contains(id)),
+  );
 
-  let mut remove = RemoveVisitor::new(import, exports);
-  // println!("remove = {:?}", remove);
-  module.visit_mut_with(&mut remove);
+  if let Some((span, message)) = import.unsupported.first() {
+    return Err(Error::Unsupported {
+      message: message.clone(),
+      location: Location::new(&cm, &fm, *span),
+    });
+  }
 
-  // println!("module = {:?}", module);
+  Ok(Parsed {
+    cm,
+    module,
+    original,
+    comments,
+    import,
+  })
+}
+
+/// Resolve `module` again in fresh globals and run `f` with its unresolved
+/// and top-level marks, the ones of the analysis are gone once it is parsed.
+fn resolve_again(
+  mut module: Module,
+  typescript: bool,
+  f: impl FnOnce(Module, Mark, Mark) -> Module,
+) -> Module {
+  GLOBALS.set(&Globals::new(), || {
+    module.visit_mut_with(&mut ResetContext);
+    let unresolved_mark = Mark::new();
+    let top_level_mark = Mark::new();
+    module.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, typescript));
+    f(module, unresolved_mark, top_level_mark)
+  })
+}
+
+/// Parse the replacement of a defined expression.
+fn parse_define(cm: &SourceMap, name: &str, value: &str) -> Result<Expr, Error> {
+  let invalid = |message: String| Error::Define {
+    name: name.to_string(),
+    message,
+  };
+  let fm = cm.new_source_file(
+    FileName::Custom(format!("define:{}", name)),
+    value.to_string(),
+  );
+  let mut parser = Parser::new(Syntax::default(), SourceFileInput::from(&*fm), None);
+  let mut expr = parser
+    .parse_expr()
+    .map_err(|err| invalid(err.kind().msg().to_string()))?;
+  if let Some(err) = parser.take_errors().into_iter().next() {
+    return Err(invalid(err.kind().msg().to_string()));
+  }
+  if expr.span().hi != fm.end_pos {
+    return Err(invalid("expected a single expression".to_string()));
+  }
+  // the replacement has no place in the source
+  expr.visit_mut_with(&mut DropSpan);
+  Ok(*expr)
+}
+
+/// Describe the exports of a module and what they depend on, without
+/// transforming it.
+pub fn analyze(source: &str, options: &Options) -> Result<Analysis, Error> {
+  let parsed = parse(source, options)?;
+  Ok(Analysis::new(&parsed.import))
+}
+
+/// Remove `exports` from a module, returns an error instead of panicking on
+/// invalid or unsupported input.
+pub fn remove_exports_with_options(
+  source: &str,
+  exports: Vec<String>,
+  options: &Options,
+) -> Result<Output, Error> {
+  let input_source_map = match &options.input_source_map {
+    Some(input) => Some(source_map::parse(input)?),
+    None => None,
+  };
 
-  // println!("{:?}", module);
+  let parsed = parse(source, options)?;
+  transform(parsed, exports, options, input_source_map.as_ref())
+}
+
+/// Remove `exports` from a parsed module and print it.
+fn transform(
+  parsed: Parsed,
+  exports: Vec<String>,
+  options: &Options,
+  input_source_map: Option<&sourcemap::SourceMap>,
+) -> Result<Output, Error> {
+  let Parsed {
+    cm,
+    mut module,
+    original,
+    comments,
+    import,
+  } = parsed;
 
-  let mut buf = vec![];
+  // untouched code would be spliced in as it is, next to rewritten code
+  if options.preserve_format
+    && (options.minify || options.compress || options.mangle || options.downlevel)
   {
-    let mut emitter = Emitter {
-      cfg: Default::default(),
-      cm: cm.clone(),
-      comments: Some(&comments),
-      wr: JsWriter::new(cm, "\n", &mut buf, None),
-    };
-    emitter.emit_module(&module).unwrap()
+    return Err(Error::Options {
+      message: "preserve_format cannot be combined with minify, compress, mangle or downlevel"
+        .to_string(),
+    });
+  }
+
+  let matcher = Matcher::new(exports, options.patterns.clone())?;
+  let mut remove = RemoveVisitor::new(import, &matcher, options.mode);
+  remove.side_effects = Matcher::new(vec![], options.side_effects.clone())?;
+  remove.side_effect_free = Matcher::new(vec![], options.side_effect_free.clone())?;
+  module.visit_mut_with(&mut remove);
+  if options.prune_locals {
+    remove.prune_locals(&mut module);
   }
-  String::from_utf8_lossy(&buf).to_string()
+  let kept = (module.body.iter())
+    .map(|x| x.span())
+    .filter(|x| !x.is_dummy())
+    .map(|x| x.lo)
+    .collect::<Vec<_>>();
+  let removed_comments = comments::strip(
+    &cm,
+    &comments,
+    &remove.removed_spans,
+    &kept,
+    options.comments,
+    module.span.lo,
+  );
+  if options.downlevel {
+    module = compat::downlevel(&comments, module, options)?;
+  }
+  if options.compress || options.mangle {
+    module = minify::minify(cm.clone(), &comments, module, options)?;
+  }
+
+  let mut as_const = using::AsConst::default();
+  module.visit_mut_with(&mut as_const);
+  let cfg = codegen::Config::default()
+    .with_target(options.target)
+    .with_minify(options.minify);
+  let mut mappings = vec![];
+  let spliced = match original {
+    Some(mut original) => {
+      original.visit_mut_with(&mut using::AsConst::default());
+      // the module is the first file, before defines
+      let fm = cm.files()[0].clone();
+      let mut diff = Diff::new(
+        cm.clone(),
+        &fm.src,
+        fm.start_pos,
+        cfg,
+        &comments,
+        removed_comments,
+        &as_const.using,
+      );
+      diff.module_items(&original.body, &module.body)?;
+      diff.finish(options.source_maps.then_some(&mut mappings))
+    }
+    None => None,
+  };
+  let (code, edits) = match spliced {
+    Some((code, edits)) => (code, Some(edits)),
+    None => {
+      let mut buf = vec![];
+      let mut emitter = Emitter {
+        cfg,
+        cm: cm.clone(),
+        comments: Some(&comments),
+        wr: UsingWriter::new(
+          JsWriter::new(
+            cm.clone(),
+            "\n",
+            &mut buf,
+            options.source_maps.then_some(&mut mappings),
+          ),
+          &as_const.using,
+        ),
+      };
+      emitter.emit_module(&module).map_err(|err| Error::Codegen {
+        message: err.to_string(),
+      })?;
+      (String::from_utf8_lossy(&buf).to_string(), None)
+    }
+  };
+
+  let map = match options.source_maps {
+    true => Some(source_map::build(&cm, &mappings, input_source_map)?),
+    false => None,
+  };
+
+  Ok(Output {
+    code,
+    map,
+    report: Report::new(remove),
+    edits,
+  })
 }
@@ -0,0 +1,93 @@
+use std::collections::{BTreeSet, HashSet};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use regex::Regex;
+
+use crate::Error;
+
+/// Matches export names or module specifiers against exact names and
+/// patterns.
+///
+/// A pattern is either a regex wrapped in slashes, e.g. `/^use[A-Z]/`, or a
+/// glob, e.g. `use*` or `*$`.
+#[derive(Debug, Default)]
+pub struct Matcher {
+  names: HashSet<String>,
+  patterns: Vec<String>,
+  globs: GlobSet,
+  glob_patterns: Vec<usize>,
+  regexes: Vec<(usize, Regex)>,
+}
+
+impl Matcher {
+  pub fn new(names: Vec<String>, patterns: Vec<String>) -> Result<Self, Error> {
+    let mut globs = GlobSetBuilder::new();
+    let mut glob_patterns = vec![];
+    let mut regexes = vec![];
+
+    for (index, pattern) in patterns.iter().enumerate() {
+      let invalid = |message: String| Error::Pattern {
+        pattern: pattern.clone(),
+        message,
+      };
+      match pattern.strip_prefix('/').and_then(|x| x.strip_suffix('/')) {
+        Some(regex) => {
+          let regex = Regex::new(regex).map_err(|err| invalid(err.to_string()))?;
+          regexes.push((index, regex));
+        }
+        None => {
+          let glob = Glob::new(pattern).map_err(|err| invalid(err.kind().to_string()))?;
+          globs.add(glob);
+          glob_patterns.push(index);
+        }
+      }
+    }
+
+    let globs = globs.build().map_err(|err| Error::Pattern {
+      pattern: err.glob().unwrap_or_default().to_string(),
+      message: err.kind().to_string(),
+    })?;
+
+    Ok(Self {
+      names: names.into_iter().collect(),
+      patterns,
+      globs,
+      glob_patterns,
+      regexes,
+    })
+  }
+
+  pub fn is_match(&self, name: &str) -> bool {
+    self.names.contains(name)
+      || self.globs.is_match(name)
+      || self.regexes.iter().any(|(_, regex)| regex.is_match(name))
+  }
+
+  /// Names and patterns that match none of `exports`.
+  pub fn unmatched<'a>(&self, exports: impl Iterator<Item = &'a String>) -> BTreeSet<String> {
+    let mut unmatched = self.names.iter().collect::<BTreeSet<_>>();
+    let mut patterns = (0..self.patterns.len()).collect::<BTreeSet<_>>();
+
+    for name in exports {
+      unmatched.remove(name);
+      for index in self.globs.matches(name) {
+        patterns.remove(&self.glob_patterns[index]);
+      }
+      for (index, regex) in &self.regexes {
+        if regex.is_match(name) {
+          patterns.remove(index);
+        }
+      }
+    }
+
+    unmatched
+      .into_iter()
+      .cloned()
+      .chain(
+        patterns
+          .into_iter()
+          .map(|index| self.patterns[index].clone()),
+      )
+      .collect()
+  }
+}
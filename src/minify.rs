@@ -0,0 +1,69 @@
+//! Compressing and mangling the transformed module.
+
+use std::rc::Rc;
+
+use swc_common::{comments::SingleThreadedComments, SourceMap};
+use swc_ecma_minifier::{
+  optimize,
+  option::{CompressOptions, ExtraOptions, MangleOptions, MinifyOptions},
+};
+use swc_ecmascript::{
+  ast::{Module, Program},
+  transforms::{fixer, hygiene},
+  visit::VisitMutWith,
+};
+
+use crate::{resolve_again, Error, Language, Options};
+
+/// Run swc's compressor and mangler over `module`, as enabled by `options`.
+pub(crate) fn minify(
+  cm: Rc<SourceMap>,
+  comments: &SingleThreadedComments,
+  module: Module,
+  options: &Options,
+) -> Result<Module, Error> {
+  if options.language() != Language::Js {
+    return Err(Error::Options {
+      message: "compress and mangle only support JavaScript without JSX".to_string(),
+    });
+  }
+
+  let module = resolve_again(module, false, |module, unresolved_mark, top_level_mark| {
+    let compress = CompressOptions {
+      module: !options.commonjs,
+      ..Default::default()
+    };
+    let mangle = MangleOptions {
+      props: None,
+      // export names must not change
+      top_level: Some(false),
+      keep_class_names: false,
+      keep_fn_names: false,
+      keep_private_props: false,
+      ie8: false,
+      safari10: false,
+      reserved: vec![],
+      eval: false,
+    };
+    let program = optimize(
+      Program::Module(module),
+      cm,
+      Some(comments),
+      None,
+      &MinifyOptions {
+        compress: options.compress.then_some(compress),
+        mangle: options.mangle.then_some(mangle),
+        ..Default::default()
+      },
+      &ExtraOptions {
+        unresolved_mark,
+        top_level_mark,
+      },
+    );
+    let mut module = program.expect_module();
+    module.visit_mut_with(&mut hygiene());
+    module.visit_mut_with(&mut fixer(Some(comments)));
+    module
+  });
+  Ok(module)
+}
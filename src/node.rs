@@ -0,0 +1,183 @@
+//! Native Node.js bindings, mirroring the JavaScript bindings of `wasm`.
+
+use napi::{
+  bindgen_prelude::{Buffer, Either},
+  Env, JsFunction, JsObject, JsString, JsUnknown, Status, ValueType,
+};
+use napi_derive::napi;
+use serde::Serialize;
+
+use crate::{Error, Job, Loader, Options};
+
+/// A module source, either a string or a UTF-8 `Buffer` read in place.
+type Source = Either<String, Buffer>;
+
+fn source_str(source: &Source) -> napi::Result<&str> {
+  match source {
+    Either::A(source) => Ok(source),
+    Either::B(buffer) => std::str::from_utf8(buffer)
+      .map_err(|err| napi::Error::new(Status::InvalidArg, format!("invalid source: {}", err))),
+  }
+}
+
+fn create_js_error(env: &Env, err: Error) -> napi::Result<JsObject> {
+  let mut error = env.create_error(napi::Error::new(Status::GenericFailure, err.message()))?;
+  error.set_named_property("code", err.code())?;
+  if let Some(loc) = err.location() {
+    error.set_named_property("start", loc.utf16_start as u32)?;
+    error.set_named_property("end", loc.utf16_end as u32)?;
+    error.set_named_property("line", loc.line as u32)?;
+    error.set_named_property("column", loc.column as u32)?;
+  }
+  Ok(error)
+}
+
+fn to_js_error(env: &Env, err: Error) -> napi::Error {
+  match create_js_error(env, err) {
+    Ok(error) => error.into_unknown().into(),
+    Err(err) => err,
+  }
+}
+
+fn to_js_output(env: &Env, output: impl Serialize) -> napi::Result<JsUnknown> {
+  env.to_js_value(&output)
+}
+
+fn from_js_options(env: &Env, options: Option<JsUnknown>) -> napi::Result<Options> {
+  match options {
+    Some(options) if !matches!(options.get_type()?, ValueType::Undefined | ValueType::Null) => env
+      .from_js_value(options)
+      .map_err(|err| napi::Error::new(Status::InvalidArg, format!("invalid options: {}", err))),
+    _ => Ok(Options::default()),
+  }
+}
+
+#[napi(js_name = "remove_exports")]
+pub fn remove_exports(env: Env, source: Source, exports: Vec<String>) -> napi::Result<String> {
+  crate::remove_exports_with_options(source_str(&source)?, exports, &Options::default())
+    .map(|output| output.code)
+    .map_err(|err| to_js_error(&env, err))
+}
+
+#[napi(js_name = "remove_exports_with_options")]
+pub fn remove_exports_with_options(
+  env: Env,
+  source: Source,
+  exports: Vec<String>,
+  options: Option<JsUnknown>,
+) -> napi::Result<JsUnknown> {
+  let options = from_js_options(&env, options)?;
+  crate::remove_exports_with_options(source_str(&source)?, exports, &options)
+    .map_err(|err| to_js_error(&env, err))
+    .and_then(|output| to_js_output(&env, output))
+}
+
+#[napi(js_name = "analyze")]
+pub fn analyze(env: Env, source: Source, options: Option<JsUnknown>) -> napi::Result<JsUnknown> {
+  let options = from_js_options(&env, options)?;
+  crate::analyze(source_str(&source)?, &options)
+    .map_err(|err| to_js_error(&env, err))
+    .and_then(|analysis| to_js_output(&env, analysis))
+}
+
+/// A [`Loader`] calling the `resolve` and `load` functions of a JS object.
+struct JsLoader<'a> {
+  env: &'a Env,
+  object: JsObject,
+}
+
+impl JsLoader<'_> {
+  fn call(&self, method: &str, args: &[&str]) -> Result<JsUnknown, String> {
+    let function = self
+      .object
+      .get_named_property::<JsFunction>(method)
+      .map_err(|_| format!("loader.{} is not a function", method))?;
+    let args = args
+      .iter()
+      .map(|x| self.env.create_string(x))
+      .collect::<napi::Result<Vec<_>>>()
+      .map_err(|err| err.reason)?;
+    function
+      .call(Some(&self.object), &args)
+      .map_err(|err| err.reason)
+  }
+
+  fn into_string(value: JsUnknown, message: &str) -> Result<String, String> {
+    match value.get_type() {
+      Ok(ValueType::String) => unsafe { value.cast::<JsString>() }
+        .into_utf8()
+        .and_then(|x| x.into_owned())
+        .map_err(|err| err.reason),
+      _ => Err(message.to_string()),
+    }
+  }
+}
+
+impl Loader for JsLoader<'_> {
+  fn resolve(&self, specifier: &str, importer: &str) -> Result<Option<String>, String> {
+    let id = self.call("resolve", &[specifier, importer])?;
+    match id.get_type() {
+      Ok(ValueType::Undefined | ValueType::Null) => Ok(None),
+      _ => Self::into_string(id, "loader.resolve must return a string").map(Some),
+    }
+  }
+
+  fn load(&self, id: &str) -> Result<String, String> {
+    Self::into_string(
+      self.call("load", &[id])?,
+      "loader.load must return a string",
+    )
+  }
+}
+
+#[napi(js_name = "remove_exports_from_graph")]
+pub fn remove_exports_from_graph(
+  env: Env,
+  entry: String,
+  exports: Vec<String>,
+  options: Option<JsUnknown>,
+  loader: JsObject,
+) -> napi::Result<JsUnknown> {
+  let options = from_js_options(&env, options)?;
+  let loader = JsLoader {
+    env: &env,
+    object: loader,
+  };
+  crate::remove_exports_from_graph(&entry, exports, &options, &loader)
+    .map_err(|err| to_js_error(&env, err))
+    .and_then(|outputs| to_js_output(&env, outputs))
+}
+
+/// Removes exports from many modules in parallel, caching their analysis
+/// between runs.
+#[napi(js_name = "Batch")]
+pub struct JsBatch {
+  batch: crate::Batch,
+}
+
+#[napi]
+impl JsBatch {
+  #[napi(constructor)]
+  pub fn new(env: Env, options: Option<JsUnknown>) -> napi::Result<Self> {
+    Ok(Self {
+      batch: crate::Batch::new(from_js_options(&env, options)?),
+    })
+  }
+
+  /// Outputs of `jobs` in the same order, or the errors they failed with.
+  #[napi]
+  pub fn run(&self, env: Env, jobs: JsUnknown) -> napi::Result<Vec<JsUnknown>> {
+    let jobs: Vec<Job> = env
+      .from_js_value(jobs)
+      .map_err(|err| napi::Error::new(Status::InvalidArg, format!("invalid jobs: {}", err)))?;
+    self
+      .batch
+      .run(&jobs)
+      .into_iter()
+      .map(|result| match result {
+        Ok(output) => to_js_output(&env, output),
+        Err(err) => create_js_error(&env, err).map(|x| x.into_unknown()),
+      })
+      .collect()
+  }
+}
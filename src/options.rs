@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Deserializer};
+use swc_ecmascript::{
+  ast::EsVersion,
+  parser::{EsConfig, Syntax, TsConfig},
+};
+
+use crate::Mode;
+
+/// Source language of the input module.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+  /// Plain JavaScript.
+  #[default]
+  Js,
+  /// JavaScript with JSX.
+  Jsx,
+  /// TypeScript.
+  Ts,
+  /// TypeScript with JSX.
+  Tsx,
+}
+
+impl Language {
+  /// Infer the language from a file name or module id, defaults to [`Language::Js`].
+  pub fn from_filename(filename: &str) -> Self {
+    // strip vite-like queries, e.g. `route.tsx?v=123`
+    let path = filename.split(['?', '#']).next().unwrap_or_default();
+    match path.rsplit_once('.').map(|(_, ext)| ext) {
+      Some("jsx") => Language::Jsx,
+      Some("ts" | "mts" | "cts") => Language::Ts,
+      Some("tsx") => Language::Tsx,
+      _ => Language::Js,
+    }
+  }
+
+  fn with_jsx(self) -> Self {
+    match self {
+      Language::Js | Language::Jsx => Language::Jsx,
+      Language::Ts | Language::Tsx => Language::Tsx,
+    }
+  }
+
+  pub(crate) fn syntax(self) -> Syntax {
+    match self {
+      Language::Js => Syntax::Es(EsConfig {
+        decorators: true,
+        explicit_resource_management: true,
+        ..Default::default()
+      }),
+      Language::Jsx => Syntax::Es(EsConfig {
+        jsx: true,
+        decorators: true,
+        explicit_resource_management: true,
+        ..Default::default()
+      }),
+      Language::Ts => Syntax::Typescript(TsConfig {
+        decorators: true,
+        ..Default::default()
+      }),
+      Language::Tsx => Syntax::Typescript(TsConfig {
+        tsx: true,
+        decorators: true,
+        ..Default::default()
+      }),
+    }
+  }
+
+  pub(crate) fn is_typescript(self) -> bool {
+    matches!(self, Language::Ts | Language::Tsx)
+  }
+
+  fn filename(self) -> &'static str {
+    match self {
+      Language::Js => "input.js",
+      Language::Jsx => "input.jsx",
+      Language::Ts => "input.ts",
+      Language::Tsx => "input.tsx",
+    }
+  }
+}
+
+/// Which comments are kept in the output.
+///
+/// Comments of removed declarations, imports and statements are always
+/// removed with them, except license comments which are moved to the top of
+/// the module.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CommentPolicy {
+  /// Keep every comment.
+  #[default]
+  All,
+  /// Keep `/*! ... */` comments and comments containing `@license`.
+  License,
+  /// Remove every comment.
+  None,
+}
+
+impl<'de> Deserialize<'de> for CommentPolicy {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Name {
+      All,
+      License,
+      None,
+    }
+
+    // `true` and `false` are accepted for `all` and `none`
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+      Bool(bool),
+      Name(Name),
+    }
+
+    Ok(match Repr::deserialize(deserializer)? {
+      Repr::Bool(true) | Repr::Name(Name::All) => CommentPolicy::All,
+      Repr::Name(Name::License) => CommentPolicy::License,
+      Repr::Bool(false) | Repr::Name(Name::None) => CommentPolicy::None,
+    })
+  }
+}
+
+/// Options for [`remove_exports_with_options`](crate::remove_exports_with_options).
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Options {
+  /// Whether the given export names are removed or kept.
+  pub mode: Mode,
+  /// Globs or `/regex/`es matching export names, in addition to the exact
+  /// names.
+  pub patterns: Vec<String>,
+  /// Also remove top-level statements other than declarations that use a
+  /// removed declaration, instead of keeping everything they reference.
+  pub prune_statements: bool,
+  /// Also remove unused side-effect free declarations inside kept
+  /// functions, and the imports only they used.
+  pub prune_locals: bool,
+  /// Global expressions replaced with constant expressions before the
+  /// analysis, e.g. `import.meta.env.SSR` => `false`. Conditions made
+  /// constant by them are folded and their dead branches removed.
+  pub define: BTreeMap<String, String>,
+  /// Modules imported for their side effects, an import of them whose
+  /// specifiers are all removed is kept as `import "source"`.
+  pub side_effects: Vec<String>,
+  /// Modules without side effects, an import of them whose specifiers are
+  /// all removed is dropped even if it matches `side_effects`.
+  pub side_effect_free: Vec<String>,
+  /// Parse the source as a CommonJS script, and remove `exports.foo = ...`,
+  /// `module.exports = { ... }` and `Object.defineProperty(exports, ...)`
+  /// exports.
+  pub commonjs: bool,
+  /// Remove top-level `using` and `await using` declarations like other
+  /// declarations. They are kept by default, since disposing of their
+  /// resources is a side effect.
+  pub drop_disposables: bool,
+  /// Name of the module, used in source maps and error messages, and to
+  /// infer the language if `syntax` is not set.
+  pub filename: Option<String>,
+  /// Source language of the module.
+  pub syntax: Option<Language>,
+  /// ECMAScript version of the output. Newer syntax is only lowered to it
+  /// with `downlevel`.
+  pub target: EsVersion,
+  /// Newest ECMAScript version accepted in the input.
+  pub input_target: EsVersion,
+  /// Lower syntax newer than `target` with swc's compat transforms. Not
+  /// supported for TypeScript.
+  pub downlevel: bool,
+  /// Parse JSX regardless of the language.
+  pub jsx: bool,
+  /// Generate a source map.
+  pub source_maps: bool,
+  /// Source map of a previous transform to chain the generated one onto.
+  pub input_source_map: Option<String>,
+  /// Emit minified code.
+  pub minify: bool,
+  /// Run swc's compressor over the module before printing it. Only
+  /// supported for JavaScript without JSX.
+  pub compress: bool,
+  /// Shorten the names of local bindings, exports are left alone. Only
+  /// supported for JavaScript without JSX.
+  pub mangle: bool,
+  /// Which comments are kept in the output.
+  pub comments: CommentPolicy,
+  /// Splice the original source instead of printing the whole module, so
+  /// that untouched code is kept byte for byte. The edits are returned in
+  /// [`Output::edits`](crate::Output::edits). Cannot be combined with
+  /// `minify`, `compress`, `mangle` or `downlevel`.
+  pub preserve_format: bool,
+}
+
+impl Default for Options {
+  fn default() -> Self {
+    Self {
+      mode: Mode::Remove,
+      patterns: vec![],
+      prune_statements: false,
+      prune_locals: false,
+      define: BTreeMap::new(),
+      side_effects: vec![],
+      side_effect_free: vec![],
+      commonjs: false,
+      drop_disposables: false,
+      filename: None,
+      syntax: None,
+      target: EsVersion::Es2022,
+      input_target: EsVersion::EsNext,
+      downlevel: false,
+      jsx: false,
+      source_maps: false,
+      input_source_map: None,
+      minify: false,
+      compress: false,
+      mangle: false,
+      comments: CommentPolicy::All,
+      preserve_format: false,
+    }
+  }
+}
+
+impl Options {
+  pub(crate) fn language(&self) -> Language {
+    let language = match (self.syntax, &self.filename) {
+      (Some(language), _) => language,
+      (None, Some(filename)) => Language::from_filename(filename),
+      (None, None) => Language::default(),
+    };
+    match self.jsx {
+      true => language.with_jsx(),
+      false => language,
+    }
+  }
+
+  pub(crate) fn filename(&self) -> String {
+    match &self.filename {
+      Some(filename) => filename.clone(),
+      None => self.language().filename().to_string(),
+    }
+  }
+}
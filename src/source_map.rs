@@ -0,0 +1,36 @@
+use swc_common::{source_map::SourceMapGenConfig, BytePos, FileName, LineCol, SourceMap};
+
+use crate::Error;
+
+struct Config;
+
+impl SourceMapGenConfig for Config {
+  fn file_name_to_source(&self, f: &FileName) -> String {
+    f.to_string()
+  }
+
+  fn inline_sources_content(&self, _: &FileName) -> bool {
+    true
+  }
+}
+
+/// Parse the source map produced by an earlier transform.
+pub(crate) fn parse(input: &str) -> Result<sourcemap::SourceMap, Error> {
+  sourcemap::SourceMap::from_slice(input.as_bytes()).map_err(|err| Error::SourceMap {
+    message: err.to_string(),
+  })
+}
+
+/// Build a v3 source map as JSON, chained onto `input` if given.
+pub(crate) fn build(
+  cm: &SourceMap,
+  mappings: &[(BytePos, LineCol)],
+  input: Option<&sourcemap::SourceMap>,
+) -> Result<String, Error> {
+  let map = cm.build_source_map_with_config(mappings, input, Config);
+  let mut buf = vec![];
+  map.to_writer(&mut buf).map_err(|err| Error::SourceMap {
+    message: err.to_string(),
+  })?;
+  Ok(String::from_utf8_lossy(&buf).to_string())
+}
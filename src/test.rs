@@ -1,4 +1,11 @@
-use crate::remove_exports;
+use std::collections::BTreeMap;
+
+use swc_ecmascript::ast::EsVersion;
+
+use crate::{
+  analyze, remove_exports, remove_exports_from_graph, remove_exports_with_options, Batch,
+  CommentPolicy, Error, ExportKind, Import, Job, Language, Loader, Mode, Options, Output,
+};
 
 fn format_code(code: &str) -> String {
   code
@@ -19,6 +26,22 @@ macro_rules! run {
   }};
 }
 
+macro_rules! run_with {
+  ($options:expr, $src:expr, $rms:expr, $ept:expr) => {{
+    let removes = $rms.into_iter().map(|x| x.to_string()).collect();
+    let result = remove_exports_with_options($src, removes, &$options).unwrap();
+    let expected = $ept;
+    assert_eq!(format_code(&result.code), format_code(&expected))
+  }};
+}
+
+fn syntax(language: Language) -> Options {
+  Options {
+    syntax: Some(language),
+    ..Default::default()
+  }
+}
+
 macro_rules! run_empty {
   ($src:expr, $rms:expr) => {
     run!($src, $rms, "")
@@ -87,7 +110,7 @@ fn remove_export_decl() {
   run!(
     "export const { foo, bar: baz, bai = 2333, ...rest } = {}",
     ["foo"],
-    "export const { bar: baz, bai = 2333, ...rest } = {};"
+    "const { foo, bar: baz, bai = 2333, ...rest } = {};\nexport { baz, bai, rest };"
   );
   run!(
     "export const { foo, bar: baz, bai = 2333, ...rest } = {}",
@@ -97,12 +120,12 @@ fn remove_export_decl() {
   run!(
     "export const { foo, bar: baz, bai = 2333, ...rest } = {}",
     ["baz"],
-    "export const { foo, bai = 2333, ...rest } = {};"
+    "const { foo, bar: baz, bai = 2333, ...rest } = {};\nexport { foo, bai, rest };"
   );
   run!(
     "export const { foo, bar: baz, bai = 2333, ...rest } = {}",
     ["bai"],
-    "export const { foo, bar: baz, ...rest } = {};"
+    "const { foo, bar: baz, bai, ...rest } = {};\nexport { foo, baz, rest };"
   );
   run!(
     "export const { foo, bar: baz, bai = 2333, ...rest } = {}",
@@ -473,3 +496,1557 @@ fn should_work_for_qwik() {
     "#
   );
 }
+
+#[test]
+fn should_work_for_typescript() {
+  run_with!(
+    syntax(Language::Ts),
+    r#"
+    import type { User } from "~/database.ts";
+    import { db } from "~/database.ts";
+    interface Props { user: User }
+    type Loader = () => Promise<Props>;
+    export const loader: Loader = async () => ({ user: await db.get() });
+    export function Page(props: Props) {}
+    "#,
+    ["loader"],
+    r#"
+    import type { User } from "~/database.ts";
+    interface Props {
+      user: User;
+    }
+    export function Page(props: Props) {}
+    "#
+  );
+
+  run_with!(
+    syntax(Language::Ts),
+    r#"
+    import { Base } from "source";
+    import Legacy = require("legacy");
+    export interface Foo extends Base {}
+    export type Bar = Legacy.Bar;
+    export enum Baz { A, B }
+    export namespace Qux { export const a = 1; }
+    declare module "source" {}
+    "#,
+    ["Foo", "Bar", "Baz", "Qux"],
+    r#"
+    declare module "source" {
+    }
+    "#
+  );
+}
+
+#[test]
+fn should_work_for_jsx() {
+  run_with!(
+    syntax(Language::Jsx),
+    r#"
+    import { ui } from "ui";
+    import { Page } from "./page";
+    import { Layout } from "./layout";
+    export const Server = () => <Page><ui.Button /></Page>;
+    export const Client = () => <Layout />;
+    "#,
+    ["Server"],
+    r#"
+    import { Layout } from "./layout";
+    export const Client = ()=><Layout/>;
+    "#
+  );
+}
+
+#[test]
+fn infer_language_from_filename() {
+  assert_eq!(Language::from_filename("route.js"), Language::Js);
+  assert_eq!(Language::from_filename("route.mjs"), Language::Js);
+  assert_eq!(Language::from_filename("route.jsx"), Language::Jsx);
+  assert_eq!(Language::from_filename("route.ts"), Language::Ts);
+  assert_eq!(Language::from_filename("route.d.mts"), Language::Ts);
+  assert_eq!(
+    Language::from_filename("app/routes/index.tsx"),
+    Language::Tsx
+  );
+  assert_eq!(Language::from_filename("route.tsx?v=123"), Language::Tsx);
+}
+
+#[test]
+fn report_errors() {
+  let options = Options::default();
+  let err = remove_exports_with_options("const a = 1;\nexport {", vec![], &options).unwrap_err();
+  assert_eq!(err.code(), "PARSE_ERROR");
+  let loc = err.location().unwrap();
+  assert_eq!((loc.line, loc.column), (2, 7));
+
+  let err = remove_exports_with_options("const é = 1;\nexport {", vec![], &options).unwrap_err();
+  let loc = err.location().unwrap();
+  assert_eq!((loc.start, loc.utf16_start), (21, 20));
+
+  let err = remove_exports_with_options("const a = <div />;", vec![], &options).unwrap_err();
+  assert!(matches!(err, Error::Parse { .. }));
+  assert_eq!(err.to_string(), "Expression expected (1:16)");
+}
+
+#[test]
+fn generate_source_map() {
+  let source = "export const foo = 1;\nexport const bar = 2;\n";
+  let options = Options {
+    filename: Some("route.js".to_string()),
+    source_maps: true,
+    ..Default::default()
+  };
+  let output = remove_exports_with_options(source, vec!["foo".to_string()], &options).unwrap();
+  assert_eq!(output.code, "export const bar = 2;\n");
+
+  let map = sourcemap::SourceMap::from_slice(output.map.unwrap().as_bytes()).unwrap();
+  assert_eq!(map.get_source(0), Some("route.js"));
+  // `bar` is on the second line of the input
+  let token = map.lookup_token(0, 13).unwrap();
+  assert_eq!((token.get_src_line(), token.get_src_col()), (1, 13));
+
+  // chain onto the map of a previous transform, which moved every line down
+  let mut builder = sourcemap::SourceMapBuilder::new(None);
+  let src = builder.add_source("route.ts");
+  for line in 0..2 {
+    for col in [0, 13] {
+      builder.add_raw(line, col, line + 5, col, Some(src), None, false);
+    }
+  }
+  let mut input = vec![];
+  builder.into_sourcemap().to_writer(&mut input).unwrap();
+
+  let options = Options {
+    input_source_map: Some(String::from_utf8(input).unwrap()),
+    ..options
+  };
+  let output = remove_exports_with_options(source, vec!["foo".to_string()], &options).unwrap();
+  let map = sourcemap::SourceMap::from_slice(output.map.unwrap().as_bytes()).unwrap();
+  assert_eq!(map.get_source(0), Some("route.ts"));
+  let token = map.lookup_token(0, 13).unwrap();
+  assert_eq!((token.get_src_line(), token.get_src_col()), (6, 13));
+
+  let options = Options {
+    input_source_map: Some("{".to_string()),
+    ..options
+  };
+  let err = remove_exports_with_options(source, vec![], &options).unwrap_err();
+  assert_eq!(err.code(), "SOURCE_MAP_ERROR");
+}
+
+#[test]
+fn apply_options() {
+  let source = "/** comment */\nexport const foo = <div />;\nexport const bar = 1;";
+
+  let options = Options {
+    filename: Some("route.jsx".to_string()),
+    ..Default::default()
+  };
+  let output = remove_exports_with_options(source, vec!["bar".to_string()], &options).unwrap();
+  assert_eq!(output.code, "/** comment */ export const foo = <div/>;\n");
+  assert_eq!(output.map, None);
+
+  let options = Options {
+    jsx: true,
+    minify: true,
+    comments: CommentPolicy::None,
+    ..Default::default()
+  };
+  let output = remove_exports_with_options(source, vec!["bar".to_string()], &options).unwrap();
+  assert_eq!(output.code, "export const foo=<div/>;");
+}
+
+#[test]
+fn report_removed() {
+  let source = r#"
+    import { db, sql as query } from "sqlite";
+    import * as fs from "node:fs";
+    import { useState } from "react";
+    const USER_ID = 114514;
+    const load = () => query(fs, USER_ID);
+    export const loader = async () => db.get(load());
+    export { load as action, useState };
+    export * as utils from "./utils";
+  "#;
+  let removes = ["loader", "action", "utils", "missing", "loader"];
+  let removes = removes.into_iter().map(String::from).collect();
+  let output = remove_exports_with_options(source, removes, &Options::default()).unwrap();
+
+  let report = output.report;
+  assert_eq!(report.removed_exports, ["action", "loader", "utils"]);
+  assert_eq!(report.removed_decls, ["USER_ID", "load", "loader"]);
+  assert_eq!(
+    report.removed_imports,
+    BTreeMap::from([
+      ("node:fs".to_string(), vec!["*".to_string()]),
+      (
+        "sqlite".to_string(),
+        vec!["db".to_string(), "sql".to_string()]
+      ),
+    ])
+  );
+  assert_eq!(report.not_found, ["missing"]);
+}
+
+#[test]
+fn keep_only_named_exports() {
+  let keep = Options {
+    mode: Mode::Keep,
+    ..Default::default()
+  };
+
+  run_with!(
+    keep,
+    r#"
+    import { db } from "sqlite";
+    import { h } from "preact";
+    const USER_ID = 114514;
+    export const loader = () => db.get(USER_ID);
+    export const meta = { title: "User" };
+    export { loader as action };
+    export { handle } from "./handle";
+    export * as utils from "./utils";
+    export * from "./shared";
+    export default function Page() { return h("div"); }
+    "#,
+    ["default", "meta"],
+    r#"
+    import { h } from "preact";
+    export const meta = {
+      title: "User"
+    };
+    export default function Page() {
+      return h("div");
+    }
+    "#
+  );
+
+  run_with!(
+    keep,
+    r#"
+    const foo = 1;
+    export default foo;
+    export { foo };
+    "#,
+    ["foo"],
+    r#"
+    const foo = 1;
+    export { foo };
+    "#
+  );
+
+  // names coming from `export *` cannot be kept
+  run_with!(
+    keep,
+    r#"
+    export * from "./server";
+    export * as client from "./client";
+    export const meta = {};
+    "#,
+    ["meta", "client"],
+    r#"
+    export * as client from "./client";
+    export const meta = {};
+    "#
+  );
+
+  let source = "export const foo = 1;";
+  let keeps = vec!["foo".to_string(), "bar".to_string()];
+  let output = remove_exports_with_options(source, keeps, &keep).unwrap();
+  assert_eq!(output.code, "export const foo = 1;\n");
+  assert_eq!(output.report.not_found, ["bar"]);
+}
+
+#[test]
+fn match_export_patterns() {
+  let options = Options {
+    patterns: vec!["use[A-Z]*".to_string(), "/\\$$/".to_string()],
+    ..Default::default()
+  };
+
+  run_with!(
+    options,
+    r#"
+    import { loader$, component$ } from "@builder-io/qwik";
+    export const useUser = loader$(() => {});
+    export const onGet$ = () => {};
+    export { useUser as useAlias };
+    export { useShared } from "./shared";
+    export const user = 1;
+    export default component$(() => {});
+    "#,
+    Vec::<String>::new(),
+    r#"
+    import { component$ } from "@builder-io/qwik";
+    export const user = 1;
+    export default component$(()=>{});
+    "#
+  );
+
+  // keep mode keeps what matches
+  let options = Options {
+    mode: Mode::Keep,
+    patterns: vec!["use[A-Z]*".to_string(), "/^on/".to_string()],
+    ..options
+  };
+  let source = "export const useUser = 1;\nexport const user = 2;";
+  let output = remove_exports_with_options(source, vec!["default".to_string()], &options).unwrap();
+  assert_eq!(output.code, "export const useUser = 1;\n");
+  assert_eq!(output.report.not_found, ["/^on/", "default"]);
+
+  let options = Options {
+    patterns: vec!["/(/".to_string()],
+    ..Default::default()
+  };
+  let err = remove_exports_with_options(source, vec![], &options).unwrap_err();
+  assert_eq!(err.code(), "INVALID_PATTERN");
+}
+
+#[test]
+fn analyze_exports() {
+  let source = r#"
+  import db from "database";
+  import { json } from "router";
+  const query = () => db.query();
+  export const loader = () => json(query());
+  export { query as fetch };
+  export { helper } from "./helper";
+  export * from "./shared";
+  export default function Page() {}
+  "#;
+  let analysis = analyze(source, &Options::default()).unwrap();
+  let import = |source: &str, imported: &str| Import {
+    source: source.to_string(),
+    imported: imported.to_string(),
+  };
+
+  let exports = analysis
+    .exports
+    .iter()
+    .map(|x| (x.name.as_str(), x.kind, x.source.as_deref()))
+    .collect::<Vec<_>>();
+  assert_eq!(
+    exports,
+    [
+      ("default", ExportKind::Default, None),
+      ("fetch", ExportKind::Ref, None),
+      ("helper", ExportKind::ReExport, Some("./helper")),
+      ("loader", ExportKind::Decl, None),
+      ("*", ExportKind::Star, Some("./shared")),
+    ]
+  );
+
+  let loader = &analysis.exports[3];
+  assert_eq!(loader.locals, ["loader"]);
+  assert_eq!(
+    loader.imports,
+    [import("database", "default"), import("router", "json")]
+  );
+  let fetch = &analysis.exports[1];
+  assert_eq!(fetch.locals, ["query"]);
+  assert_eq!(fetch.imports, [import("database", "default")]);
+
+  let query = &analysis.declarations["query"];
+  assert_eq!(query.refs, ["db"]);
+  assert_eq!(query.import, None);
+  assert_eq!(
+    analysis.declarations["db"].import,
+    Some(import("database", "default"))
+  );
+
+  // nothing is transformed, but errors are still reported
+  let err = analyze("export {", &Options::default()).unwrap_err();
+  assert_eq!(err.code(), "PARSE_ERROR");
+}
+
+#[test]
+fn prune_statements() {
+  let options = Options {
+    prune_statements: true,
+    ..Default::default()
+  };
+
+  run_with!(
+    options,
+    r#"
+    import { db } from "database";
+    import { registry } from "registry";
+    import { log } from "logger";
+    db.connect();
+    const action = () => db.query();
+    registry.add(action);
+    export const loader = () => action();
+    loader.hydrate = true;
+    log("loaded");
+    export { action };
+    "#,
+    ["loader", "action"],
+    r#"
+    import { log } from "logger";
+    log("loaded");
+    "#
+  );
+
+  // statements using kept declarations are kept
+  run_with!(
+    options,
+    r#"
+    const foo = 1;
+    const bar = 2;
+    console.log(foo, bar);
+    export { foo, bar };
+    "#,
+    ["foo"],
+    r#"
+    const foo = 1;
+    const bar = 2;
+    console.log(foo, bar);
+    export { bar };
+    "#
+  );
+
+  // unless they use a removed exported declaration
+  run_with!(
+    options,
+    r#"
+    import { registry } from "r";
+    export function action() {}
+    registry.add(action);
+    export function Page() { return registry; }
+    "#,
+    ["action"],
+    r#"
+    import { registry } from "r";
+    export function Page() {
+      return registry;
+    }
+    "#
+  );
+
+  // and other statements than expressions
+  run_with!(
+    options,
+    r#"
+    import { cache } from "cache";
+    export const loader = () => cache.get();
+    if (loader.cached) {
+      cache.clear();
+    }
+    for (const key of cache.keys(loader)) {}
+    switch (loader.length) {}
+    "#,
+    ["loader"],
+    ""
+  );
+
+  // but not the ones hoisting a `var` binding out of them
+  run_with!(
+    options,
+    r#"
+    const limit = 10;
+    export const loader = () => limit;
+    for (var i = 0; i < limit; i++) {}
+    export const count = () => i;
+    "#,
+    ["loader"],
+    r#"
+    const limit = 10;
+    for(var i = 0; i < limit; i++){}
+    export const count = ()=>i;
+    "#
+  );
+
+  // disabled by default
+  run!(
+    r#"
+    const foo = 1;
+    register(foo);
+    export { foo };
+    "#,
+    ["foo"],
+    r#"
+    const foo = 1;
+    register(foo);
+    "#
+  );
+}
+
+#[test]
+fn pure_annotations() {
+  run!(
+    r#"
+    import { createLoader, register } from "server";
+    const server = /*#__PURE__*/ createLoader(() => {});
+    /*#__PURE__*/ register(server);
+    const client = createLoader(() => {});
+    export const loader = () => {};
+    "#,
+    ["loader"],
+    r#"
+    import { createLoader } from "server";
+    const client = createLoader(()=>{});
+    "#
+  );
+
+  run!(
+    r#"
+    export const kept = 1;
+    /*#__NO_SIDE_EFFECTS__*/ function wrap(fn) { return fn; }
+    const unused = wrap(() => {});
+    const used = wrap(() => {});
+    export { used };
+    "#,
+    ["used"],
+    "export const kept = 1;"
+  );
+
+  // impure arguments are kept
+  run!(
+    r#"
+    import { createLoader, init } from "server";
+    const server = /*#__PURE__*/ createLoader(init());
+    "#,
+    Vec::<String>::new(),
+    r#"
+    import { createLoader, init } from "server";
+    const server = /*#__PURE__*/ createLoader(init());
+    "#
+  );
+}
+
+#[test]
+fn keep_side_effect_imports() {
+  let options = Options {
+    side_effects: vec!["./polyfills/*".to_string(), "@scope/**".to_string()],
+    side_effect_free: vec!["@scope/utils".to_string()],
+    ..Default::default()
+  };
+
+  run_with!(
+    options,
+    r#"
+    import { fetch } from "./polyfills/fetch";
+    import { format } from "@scope/date";
+    import { noop } from "@scope/utils";
+    import { db } from "sqlite";
+    import "./style.css";
+    export const loader = () => [fetch, format, noop, db];
+    "#,
+    ["loader"],
+    r#"
+    import "./polyfills/fetch";
+    import "@scope/date";
+    import "./style.css";
+    "#
+  );
+}
+
+#[test]
+fn preserve_rest_semantics() {
+  // removed keys are kept as unused bindings so `rest` does not change
+  run!(
+    r#"
+    import { heavy } from "server";
+    const { foo = heavy(), bar: { baz }, ...rest } = obj;
+    export const loader = () => [foo, baz];
+    export { rest };
+    "#,
+    ["loader"],
+    r#"
+    const { foo, bar: {}, ...rest } = obj;
+    export { rest };
+    "#
+  );
+
+  // nested patterns keep their shape, which throws on a nullish value
+  run!(
+    "export const { foo: { bar }, baz: [bai], qux: { quux } = {}, ...rest } = obj;",
+    ["bar", "bai", "quux"],
+    "export const { foo: {}, baz: [], qux: {} = {}, ...rest } = obj;"
+  );
+  // unless their default uses a removed declaration
+  run!(
+    "const empty = {};\nexport const { foo: { bar } = empty, ...rest } = obj;",
+    ["bar"],
+    "const { foo: bar, ...rest } = obj;\nexport { rest };"
+  );
+
+  // nested rest patterns
+  run!(
+    "export const { a: { b, ...c }, ...d } = obj;",
+    ["b"],
+    "const { a: { b, ...c }, ...d } = obj;\nexport { c, d };"
+  );
+  run!(
+    "export const { a: { b, ...c }, d } = obj;",
+    ["b", "c"],
+    "export const { d } = obj;"
+  );
+
+  // array holes before a rest element are kept
+  run!(
+    "export const [a, b, ...rest] = arr;",
+    ["a", "b"],
+    "export const [, , ...rest] = arr;"
+  );
+  run!(
+    "export const [a, [b, ...c], ...rest] = arr;",
+    ["b"],
+    "export const [a, [, ...c], ...rest] = arr;"
+  );
+}
+
+#[test]
+fn remove_writes() {
+  run!(
+    r#"
+    import { handler } from "server";
+    export let count = 0;
+    count = 1;
+    count++;
+    const loader = () => {};
+    loader.handler = handler;
+    Object.assign(loader, { cache: true });
+    export { loader };
+    "#,
+    ["count", "loader"],
+    ""
+  );
+
+  // writes of impure values are kept, and keep their target
+  run!(
+    r#"
+    const loader = () => {};
+    loader.handler = createHandler();
+    export { loader };
+    "#,
+    ["loader"],
+    r#"
+    const loader = ()=>{};
+    loader.handler = createHandler();
+    "#
+  );
+
+  // writes to kept decls keep what they write
+  run!(
+    r#"
+    const handler = () => {};
+    export const loader = () => {};
+    loader.handler = handler;
+    export { handler };
+    "#,
+    ["handler"],
+    r#"
+    const handler = ()=>{};
+    export const loader = ()=>{};
+    loader.handler = handler;
+    "#
+  );
+
+  // writes to globals are kept
+  run!(
+    r#"
+    const handler = () => {};
+    window.handler = handler;
+    export { handler };
+    "#,
+    ["handler"],
+    r#"
+    const handler = ()=>{};
+    window.handler = handler;
+    "#
+  );
+
+  // writes of removed exported declarations cannot be kept
+  run!(
+    r#"
+    import { registry } from "r";
+    registry.loader = loader;
+    export function loader() {}
+    export const a = 1;
+    "#,
+    ["loader"],
+    r#"
+    export const a = 1;
+    "#
+  );
+}
+
+#[test]
+fn prune_locals() {
+  let options = Options {
+    prune_locals: true,
+    ..Default::default()
+  };
+
+  run_with!(
+    options,
+    r#"
+    import { db } from "server";
+    import { h } from "ui";
+    import { unused } from "other";
+    export function Component() {
+      const query = () => db.query();
+      function helper() { return query(); }
+      class Cache {}
+      const impure = sideEffect();
+      const kept = 1, dropped = 2;
+      return h("div", kept);
+    }
+    export const loader = () => db.query();
+    "#,
+    ["loader"],
+    r#"
+    import { h } from "ui";
+    import { unused } from "other";
+    export function Component() {
+      const impure = sideEffect();
+      const kept = 1;
+      return h("div", kept);
+    }
+    "#
+  );
+
+  // shadowed names are told apart
+  run_with!(
+    options,
+    r#"
+    export const foo = () => {
+      const x = 1;
+      return () => { const x = 2; return x; };
+    };
+    "#,
+    Vec::<String>::new(),
+    r#"
+    export const foo = ()=>{
+      return ()=>{
+        const x = 2;
+        return x;
+      };
+    };
+    "#
+  );
+}
+
+#[test]
+fn define_constants() {
+  let options = Options {
+    define: BTreeMap::from([
+      ("import.meta.env.SSR".to_string(), "false".to_string()),
+      ("typeof window".to_string(), "\"object\"".to_string()),
+      (
+        "process.env.NODE_ENV".to_string(),
+        "\"production\"".to_string(),
+      ),
+    ]),
+    ..Default::default()
+  };
+
+  run_with!(
+    options,
+    r#"
+    "use strict";
+    import { db } from "server";
+    import { render } from "client";
+    import { warn } from "debug";
+    if (import.meta.env.SSR) {
+      db.connect();
+    }
+    if (typeof window === "undefined") db.close();
+    else render();
+    process.env.NODE_ENV !== "production" && warn();
+    export const env = import.meta.env.SSR ? "server" : "client";
+    "#,
+    Vec::<String>::new(),
+    r#"
+    "use strict";
+    import { render } from "client";
+    render();
+    export const env = "client";
+    "#
+  );
+
+  // bindings whose initializer has side effects are kept
+  run_with!(
+    options,
+    r#"
+    const conn = connect();
+    const label = "users";
+    if (import.meta.env.SSR) {
+      conn.query(label);
+    }
+    export const a = 1;
+    "#,
+    Vec::<String>::new(),
+    r#"
+    const conn = connect();
+    export const a = 1;
+    "#
+  );
+
+  // local bindings are not replaced
+  run_with!(
+    options,
+    "export function foo(window) { return typeof window; }",
+    Vec::<String>::new(),
+    "export function foo(window) {\nreturn typeof window;\n}"
+  );
+
+  let options = Options {
+    define: BTreeMap::from([("DEV".to_string(), "true true".to_string())]),
+    ..Default::default()
+  };
+  let err = remove_exports_with_options("", vec![], &options).unwrap_err();
+  assert_eq!(err.code(), "INVALID_DEFINE");
+}
+
+#[test]
+fn remove_commonjs_exports() {
+  let options = Options {
+    commonjs: true,
+    ..Default::default()
+  };
+
+  run_with!(
+    options,
+    r#"
+    "use strict";
+    const { db } = require("sqlite");
+    const fs = require("fs");
+    const { render } = require("ui");
+    const loader = () => db.query();
+    exports.loader = loader;
+    module.exports.action = () => fs.writeFileSync("x");
+    Object.defineProperty(exports, "meta", {
+      enumerable: true,
+      get: function () { return db.meta; },
+    });
+    exports.Component = () => render();
+    "#,
+    ["loader", "action", "meta"],
+    r#"
+    "use strict";
+    const { render } = require("ui");
+    exports.Component = ()=>render();
+    "#
+  );
+
+  run_with!(
+    options,
+    r#"
+    const { db } = require("sqlite");
+    function loader() { return db.query(); }
+    function Component() {}
+    module.exports = { loader, Component, action: () => db.write() };
+    "#,
+    ["loader", "action"],
+    r#"
+    function Component() {}
+    module.exports = {
+      Component
+    };
+    "#
+  );
+
+  // a local `exports` is not the module's
+  run_with!(
+    options,
+    r#"
+    const exports = {};
+    exports.loader = 1;
+    module.exports = exports;
+    "#,
+    ["loader"],
+    r#"
+    const exports = {};
+    exports.loader = 1;
+    module.exports = exports;
+    "#
+  );
+
+  // the interop flag is not an export
+  let keep = Options {
+    mode: Mode::Keep,
+    ..options.clone()
+  };
+  run_with!(
+    keep,
+    r#"
+    Object.defineProperty(exports, "__esModule", { value: true });
+    exports.__esModule = true;
+    exports.loader = 1;
+    exports.default = 2;
+    "#,
+    ["default"],
+    r#"
+    Object.defineProperty(exports, "__esModule", {
+      value: true
+    });
+    exports.__esModule = true;
+    exports.default = 2;
+    "#
+  );
+
+  let source = "const { db } = require(\"sqlite\");\nexports.loader = () => db;";
+  let output = remove_exports_with_options(source, vec!["loader".to_string()], &options).unwrap();
+  assert_eq!(output.code, "");
+  assert_eq!(output.report.removed_exports, ["loader"]);
+  assert_eq!(
+    output.report.removed_imports,
+    BTreeMap::from([("sqlite".to_string(), vec!["db".to_string()])])
+  );
+}
+
+struct MemoryLoader(BTreeMap<&'static str, &'static str>);
+
+impl Loader for MemoryLoader {
+  fn resolve(&self, specifier: &str, _importer: &str) -> Result<Option<String>, String> {
+    match specifier.strip_prefix("./") {
+      Some(id) => Ok(Some(format!("{}.js", id))),
+      None => Ok(None),
+    }
+  }
+
+  fn load(&self, id: &str) -> Result<String, String> {
+    match self.0.get(id) {
+      Some(source) => Ok(source.to_string()),
+      None => Err("not found".to_string()),
+    }
+  }
+}
+
+#[test]
+fn follow_re_exports() {
+  let loader = MemoryLoader(BTreeMap::from([
+    (
+      "route.js",
+      r#"
+      export { loader as routeLoader, meta } from "./server";
+      export * from "./actions";
+      export * from "package";
+      export default function Page() {}
+      "#,
+    ),
+    (
+      "server.js",
+      r#"
+      import { db } from "database";
+      export const loader = () => db.query();
+      export const meta = {};
+      export const unused = 1;
+      "#,
+    ),
+    ("actions.js", r#"export * from "./forms";"#),
+    (
+      "forms.js",
+      r#"
+      import { db } from "database";
+      export const action = () => db.write();
+      export const validate = () => {};
+      "#,
+    ),
+  ]));
+
+  let exports = vec!["routeLoader".to_string(), "action".to_string()];
+  let outputs =
+    remove_exports_from_graph("route.js", exports, &Options::default(), &loader).unwrap();
+  let outputs = outputs
+    .iter()
+    .map(|(id, output)| (id.as_str(), format_code(&output.code)))
+    .collect::<Vec<_>>();
+  assert_eq!(
+    outputs,
+    [
+      ("forms.js", "export const validate = ()=>{};".to_string()),
+      (
+        "route.js",
+        format_code(
+          r#"
+          export { meta } from "./server";
+          export * from "./actions";
+          export * from "package";
+          export default function Page() {}
+          "#
+        )
+      ),
+      (
+        "server.js",
+        "export const meta = {};\nexport const unused = 1;".to_string()
+      ),
+    ]
+  );
+
+  // the input source map only belongs to the entry
+  let options = Options {
+    source_maps: true,
+    input_source_map: Some(
+      r#"{"version":3,"sources":["route.ts"],"names":[],"mappings":"AAAA"}"#.to_string(),
+    ),
+    ..Default::default()
+  };
+  let exports = vec!["routeLoader".to_string()];
+  let outputs = remove_exports_from_graph("route.js", exports, &options, &loader).unwrap();
+  let map = outputs["server.js"].map.as_ref().unwrap();
+  let map = sourcemap::SourceMap::from_slice(map.as_bytes()).unwrap();
+  assert_eq!(map.sources().collect::<Vec<_>>(), ["server.js"]);
+
+  let err = remove_exports_from_graph("missing.js", vec![], &Options::default(), &loader);
+  assert_eq!(err.unwrap_err().code(), "LOAD_ERROR");
+}
+
+#[test]
+fn follow_re_exports_in_keep_mode() {
+  let loader = MemoryLoader(BTreeMap::from([
+    (
+      "route.js",
+      r#"
+      export * from "./shared";
+      export { handle } from "./handle";
+      export const loader = () => {};
+      export default function Page() {}
+      "#,
+    ),
+    (
+      "shared.js",
+      r#"
+      export const meta = {};
+      export const other = 2;
+      export default 3;
+      export * from "./nested";
+      "#,
+    ),
+    (
+      "nested.js",
+      "export const title = \"\";\nexport const deep = 1;",
+    ),
+    (
+      "handle.js",
+      "export const handle = 1;\nexport const rest = 2;",
+    ),
+  ]));
+
+  let options = Options {
+    mode: Mode::Keep,
+    ..Default::default()
+  };
+  let exports = vec![
+    "default".to_string(),
+    "meta".to_string(),
+    "title".to_string(),
+  ];
+  let outputs = remove_exports_from_graph("route.js", exports, &options, &loader).unwrap();
+  assert_eq!(outputs["route.js"].report.not_found, ["meta", "title"]);
+  let outputs = outputs
+    .iter()
+    .map(|(id, output)| (id.as_str(), format_code(&output.code)))
+    .collect::<Vec<_>>();
+  assert_eq!(
+    outputs,
+    [
+      ("handle.js", "export const rest = 2;".to_string()),
+      ("nested.js", "export const title = \"\";".to_string()),
+      (
+        "route.js",
+        format_code(
+          r#"
+          export * from "./shared";
+          export default function Page() {}
+          "#
+        )
+      ),
+      (
+        "shared.js",
+        format_code(
+          r#"
+          export const meta = {};
+          export default 3;
+          export * from "./nested";
+          "#
+        )
+      ),
+    ]
+  );
+}
+
+#[test]
+fn batch_transform() {
+  let options = Options {
+    source_maps: true,
+    prune_statements: true,
+    ..Default::default()
+  };
+  let job = |filename: &str, source: &str, exports: &[&str]| Job {
+    filename: filename.to_string(),
+    source: source.to_string(),
+    exports: exports.iter().map(|x| x.to_string()).collect(),
+    input_source_map: None,
+  };
+  let route = r#"
+    import { db } from "database";
+    // loads the user
+    export const loader = () => db.query();
+    /** the page */
+    export default function Page(props: { id: string }) {}
+    "#;
+  let mut jobs = vec![
+    job("route.tsx", route, &["loader"]),
+    job(
+      "server.ts",
+      "export const a = 1; export const b = 2;",
+      &["b"],
+    ),
+    job("broken.js", "export {", &[]),
+  ];
+
+  let batch = Batch::new(options.clone());
+  let expect = |jobs: &[Job]| {
+    jobs
+      .iter()
+      .map(|job| {
+        let options = Options {
+          filename: Some(job.filename.clone()),
+          ..options.clone()
+        };
+        remove_exports_with_options(&job.source, job.exports.clone(), &options)
+      })
+      .collect::<Vec<_>>()
+  };
+  assert_eq!(batch.run(&jobs), expect(&jobs));
+  assert_eq!(batch.cached(), 2);
+
+  // unchanged sources are restored from the cache
+  assert_eq!(batch.run(&jobs), expect(&jobs));
+  jobs[0].exports = vec!["default".to_string()];
+  jobs[1].source = "export const a = 1; export const c = 3;".to_string();
+  jobs[1].exports = vec!["c".to_string()];
+  let outputs = batch.run(&jobs);
+  assert_eq!(outputs, expect(&jobs));
+  assert_eq!(
+    format_code(&outputs[1].as_ref().unwrap().code),
+    "export const a = 1;"
+  );
+  assert_eq!(batch.cached(), 2);
+  assert_eq!(outputs[2].as_ref().unwrap_err().code(), "PARSE_ERROR");
+
+  // the least recently used modules are dropped beyond the capacity
+  let batch = Batch::with_capacity(options.clone(), 1);
+  batch.run(&jobs);
+  assert_eq!(batch.cached(), 1);
+
+  // input source maps belong to each job
+  let input = r#"{"version":3,"sources":["server.src.ts"],"names":[],"mappings":"AAAA"}"#;
+  jobs[1].input_source_map = Some(input.to_string());
+  let outputs = Batch::new(options.clone()).run(&jobs[..2]);
+  let map = |output: &Result<Output, Error>| {
+    let map = output.as_ref().unwrap().map.clone().unwrap();
+    let map = sourcemap::SourceMap::from_slice(map.as_bytes()).unwrap();
+    map.sources().map(|x| x.to_string()).collect::<Vec<_>>()
+  };
+  assert_eq!(map(&outputs[0]), ["route.tsx"]);
+  assert_eq!(map(&outputs[1]), ["server.src.ts"]);
+  let options = Options {
+    input_source_map: Some(input.to_string()),
+    ..options
+  };
+  let outputs = Batch::new(options).run(&jobs);
+  assert_eq!(outputs[0].as_ref().unwrap_err().code(), "INVALID_OPTIONS");
+}
+
+#[test]
+fn preserve_format() {
+  let options = Options {
+    preserve_format: true,
+    ..Default::default()
+  };
+  let run = |source: &str, exports: &[&str]| {
+    let exports = exports.iter().map(|x| x.to_string()).collect();
+    remove_exports_with_options(source, exports, &options).unwrap()
+  };
+
+  let source = r#"import { database } from "backend";
+import { useState } from "frontend";
+
+const USER_NAME = "admin";
+
+export const findUser = async () => {
+  return await database.findUser(USER_NAME);
+};
+
+export default function () {
+  const [count, setCount] = useState(0);
+  return [ 1,2,
+    3 ];
+}
+"#;
+  let output = run(source, &["findUser"]);
+  assert_eq!(
+    output.code,
+    r#"import { useState } from "frontend";
+
+export default function () {
+  const [count, setCount] = useState(0);
+  return [ 1,2,
+    3 ];
+}
+"#
+  );
+  let edits = output.edits.unwrap();
+  assert_eq!(edits.len(), 3);
+  assert_eq!((edits[0].start, edits[0].end), (0, 36));
+  assert_eq!(edits[0].text, "");
+
+  // JavaScript strings are indexed in UTF-16 code units
+  let source = "const s = \"é\";\nexport const foo = 1;\nexport const bar = s;\n";
+  let output = run(source, &["foo"]);
+  let edits = output.edits.unwrap();
+  assert_eq!((edits[0].start, edits[0].end), (16, 38));
+  assert_eq!((edits[0].utf16_start, edits[0].utf16_end), (15, 37));
+  let mut units = source.encode_utf16().collect::<Vec<_>>();
+  units.splice(edits[0].utf16_start..edits[0].utf16_end, []);
+  assert_eq!(String::from_utf16(&units).unwrap(), output.code);
+
+  // rewritten code cannot be spliced next to untouched code
+  for options in [
+    Options {
+      minify: true,
+      ..options.clone()
+    },
+    Options {
+      mangle: true,
+      ..options.clone()
+    },
+    Options {
+      downlevel: true,
+      ..options.clone()
+    },
+  ] {
+    let err = remove_exports_with_options(source, vec![], &options).unwrap_err();
+    assert_eq!(err.code(), "INVALID_OPTIONS");
+  }
+
+  // declarators, patterns and specifiers
+  let output = run(
+    "export const a = 1,  b = 2, c = 3;\nexport const [d,  e] = f, { g, h: [i] } = j;\nexport { k,  l };\n",
+    &["a", "c", "e", "i", "l"],
+  );
+  assert_eq!(
+    output.code,
+    "export const b = 2;\nexport const [d,  ] = f, { g } = j;\nexport { k };\n"
+  );
+
+  // named imports next to a default one
+  let output = run(
+    "import a, { b, c } from \"x\";\nimport d, { e } from \"y\";\nexport const f = [a, b, d];\nexport const g = () => c + e;\n",
+    &["g"],
+  );
+  assert_eq!(
+    output.code,
+    "import a, { b } from \"x\";\nimport d from \"y\";\nexport const f = [a, b, d];\n"
+  );
+
+  // kept rest contents are printed
+  let output = run(
+    "export const { a = 1, ...rest } = obj; // keep\nfoo();\n",
+    &["a"],
+  );
+  assert_eq!(
+    output.code,
+    "const { a, ...rest } = obj;\nexport { rest }; // keep\nfoo();\n"
+  );
+
+  // source maps point into the untouched code
+  let options = Options {
+    source_maps: true,
+    ..options.clone()
+  };
+  let source = "export const foo = 1;\nexport const bar = 2;\n";
+  let output = remove_exports_with_options(source, vec!["foo".to_string()], &options).unwrap();
+  assert_eq!(output.code, "export const bar = 2;\n");
+  let map = sourcemap::SourceMap::from_slice(output.map.unwrap().as_bytes()).unwrap();
+  let token = map.lookup_token(0, 13).unwrap();
+  assert_eq!((token.get_src_line(), token.get_src_col()), (1, 13));
+}
+
+#[test]
+fn comment_policies() {
+  let source = r#"/*! banner */
+/** the user */
+import { db } from "database";
+// loads the user
+export const loader = () => db.query(); // query
+/**
+ * @license MIT
+ */
+export const action = 1, /* the page */ page = 2;
+/** the component */
+export default function Page() {
+  // render
+  return null;
+}
+"#;
+  let run = |options: &Options| {
+    remove_exports_with_options(
+      source,
+      vec!["loader".to_string(), "page".to_string()],
+      options,
+    )
+    .unwrap()
+    .code
+  };
+
+  let options = Options::default();
+  assert_eq!(
+    run(&options),
+    r#"/*! banner */ /**
+ * @license MIT
+ */ export const action = 1;
+/** the component */ export default function Page() {
+    // render
+    return null;
+}
+"#
+  );
+  let options = Options {
+    comments: CommentPolicy::License,
+    ..Default::default()
+  };
+  assert_eq!(
+    run(&options),
+    r#"/*! banner */ /**
+ * @license MIT
+ */ export const action = 1;
+export default function Page() {
+    return null;
+}
+"#
+  );
+
+  let options = Options {
+    preserve_format: true,
+    ..Default::default()
+  };
+  assert_eq!(
+    run(&options),
+    r#"/*! banner */
+/**
+ * @license MIT
+ */
+export const action = 1;
+/** the component */
+export default function Page() {
+  // render
+  return null;
+}
+"#
+  );
+  let options = Options {
+    comments: CommentPolicy::License,
+    ..options
+  };
+  assert_eq!(
+    run(&options),
+    r#"/*! banner */
+/**
+ * @license MIT
+ */
+export const action = 1;
+export default function Page() {
+  return null;
+}
+"#
+  );
+  let options = Options {
+    comments: CommentPolicy::None,
+    ..options
+  };
+  assert_eq!(
+    run(&options),
+    "export const action = 1;\nexport default function Page() {\n  return null;\n}\n"
+  );
+
+  // only the comments next to a removed item are removed with it
+  let source = "export const a = 1 // t\n/** b */\nexport const b = 2;\nexport const c = 3;\n";
+  let options = Options {
+    preserve_format: true,
+    ..Default::default()
+  };
+  let output =
+    remove_exports_with_options(source, vec!["a".to_string(), "b".to_string()], &options).unwrap();
+  assert_eq!(output.code, "export const c = 3;\n");
+
+  // a comment after a removed item on its line can lead the next one
+  let source = "/** a */ export const a = 1; /** b */ export const b = 2;\n";
+  let run = |options: &Options| {
+    remove_exports_with_options(source, vec!["a".to_string()], options)
+      .unwrap()
+      .code
+  };
+  assert_eq!(run(&options), "/** b */ export const b = 2;\n");
+  assert_eq!(run(&Options::default()), "/** b */ export const b = 2;\n");
+}
+
+#[test]
+fn minify_output() {
+  let source = r#"
+    import { db } from "database";
+    export const loader = () => db.query();
+    export function format(value) {
+      const prefix = "value: ";
+      if (true) {
+        return prefix + value;
+      }
+    }
+  "#;
+  let run =
+    |options: &Options| remove_exports_with_options(source, vec!["loader".to_string()], options);
+
+  let options = Options {
+    minify: true,
+    ..Default::default()
+  };
+  assert_eq!(
+    run(&options).unwrap().code,
+    r#"export function format(value){const prefix="value: ";if(true){return prefix+value;}}"#
+  );
+  let options = Options {
+    compress: true,
+    mangle: true,
+    ..options
+  };
+  assert_eq!(
+    run(&options).unwrap().code,
+    r#"export function format(r){return"value: "+r;}"#
+  );
+
+  let options = Options {
+    syntax: Some(Language::Ts),
+    ..options
+  };
+  assert_eq!(run(&options).unwrap_err().code(), "INVALID_OPTIONS");
+}
+
+#[test]
+fn downlevel_output() {
+  let source = r#"
+    import { db } from "database";
+    export const loader = () => db.query();
+    export const name = (user) => user?.name ?? "anonymous";
+  "#;
+  let run =
+    |options: &Options| remove_exports_with_options(source, vec!["loader".to_string()], options);
+
+  let options = Options {
+    target: EsVersion::Es2019,
+    ..Default::default()
+  };
+  assert_eq!(
+    run(&options).unwrap().code,
+    "export const name = (user)=>user?.name ?? \"anonymous\";\n"
+  );
+  let options = Options {
+    downlevel: true,
+    ..options
+  };
+  assert_eq!(
+    run(&options).unwrap().code,
+    r#"export const name = (user)=>{
+    var _user_name;
+    return (_user_name = user === null || user === void 0 ? void 0 : user.name) !== null && _user_name !== void 0 ? _user_name : "anonymous";
+};
+"#
+  );
+
+  let options = Options {
+    target: EsVersion::Es5,
+    ..options
+  };
+  assert!(run(&options)
+    .unwrap()
+    .code
+    .starts_with("export var name = function(user) {\n"));
+
+  // decorators and `using` are newer than every edition
+  let source = r#"
+    import { dec, open } from "lib";
+    @dec class Model {}
+    using file = open();
+    export const model = () => [Model, file];
+  "#;
+  let options = Options {
+    target: EsVersion::Es2015,
+    ..options
+  };
+  let code = remove_exports_with_options(source, vec![], &options)
+    .unwrap()
+    .code;
+  assert!(code.contains("_apply_decs_2203_r(Model, [], [\n"));
+  assert!(code.contains("var file = _using(_stack, open());\n"));
+  assert!(!code.contains("@dec") && !code.contains("using file"));
+
+  let options = Options {
+    syntax: Some(Language::Ts),
+    ..options
+  };
+  assert_eq!(run(&options).unwrap_err().code(), "INVALID_OPTIONS");
+}
+
+#[test]
+fn using_declarations() {
+  let source = r#"
+    import { db } from "database";
+    import { lock } from "lock";
+    await using conn = await db.connect();
+    using guard = lock();
+    export const loader = () => conn.query();
+    export const action = () => guard.release();
+  "#;
+  let run = |options: &Options| {
+    remove_exports_with_options(
+      source,
+      vec!["loader".to_string(), "action".to_string()],
+      options,
+    )
+  };
+
+  let output = run(&Options::default()).unwrap();
+  assert_eq!(
+    output.code,
+    "import { db } from \"database\";\nimport { lock } from \"lock\";\nawait using conn = await db.connect();\nusing guard = lock();\n"
+  );
+  assert_eq!(output.report.removed_decls, ["action", "loader"]);
+
+  // kept along with the other exports
+  let output = remove_exports("using res = open(); export const a = 1;", vec![]);
+  assert_eq!(output, "using res = open();\nexport const a = 1;\n");
+  let output = remove_exports("function f() { using res = open(); }", vec![]);
+  assert_eq!(output, "function f() {\n    using res = open();\n}\n");
+
+  let options = Options {
+    drop_disposables: true,
+    ..Default::default()
+  };
+  let output = run(&options).unwrap();
+  assert_eq!(output.code, "");
+  assert_eq!(
+    output.report.removed_decls,
+    ["action", "conn", "guard", "loader"]
+  );
+
+  let options = Options {
+    preserve_format: true,
+    ..options
+  };
+  let source = "using a = open(), b = open();\nexport const foo = () => a;\n";
+  let output = remove_exports_with_options(source, vec!["foo".to_string()], &options).unwrap();
+  assert_eq!(output.code, "using b = open();\n");
+
+  let options = Options {
+    minify: true,
+    ..Default::default()
+  };
+  let output = run(&options).unwrap();
+  assert_eq!(
+    output.code,
+    r#"import{db}from"database";import{lock}from"lock";await using conn=await db.connect();using guard=lock();"#
+  );
+}
@@ -0,0 +1,195 @@
+//! `using` declarations with their semicolon.
+//!
+//! swc parses the semicolon of a `using` statement as an empty statement of
+//! its own, and prints the declaration without one.
+
+use std::collections::HashMap;
+
+use swc_common::{BytePos, Span};
+use swc_ecmascript::{
+  ast::{Decl, ModuleItem, Stmt, VarDecl, VarDeclKind},
+  codegen::text_writer::WriteJs,
+  visit::{VisitMut, VisitMutWith},
+};
+
+type Result = std::io::Result<()>;
+
+fn merge_semicolons<T>(n: &mut Vec<T>, stmt: fn(&mut T) -> Option<&mut Stmt>) {
+  let mut merged = Vec::with_capacity(n.len());
+  // index of the last `using` declaration, while it has no semicolon
+  let mut using = None;
+  for mut item in std::mem::take(n) {
+    match (stmt(&mut item), using.take()) {
+      // the parser never eats the semicolon after the declaration, so the
+      // first empty statement following it is that one
+      (Some(Stmt::Empty(e)), Some(i)) => {
+        if let Some(Stmt::Decl(Decl::Using(u))) = stmt(&mut merged[i]) {
+          u.span.hi = e.span.hi;
+        }
+        continue;
+      }
+      (Some(Stmt::Decl(Decl::Using(_))), _) => using = Some(merged.len()),
+      _ => {}
+    }
+    merged.push(item);
+  }
+  *n = merged;
+}
+
+/// Make the empty statement following a `using` declaration its semicolon.
+pub struct MergeSemicolon;
+
+impl VisitMut for MergeSemicolon {
+  fn visit_mut_stmts(&mut self, n: &mut Vec<Stmt>) {
+    n.visit_mut_children_with(self);
+    merge_semicolons(n, |x| Some(x));
+  }
+
+  fn visit_mut_module_items(&mut self, n: &mut Vec<ModuleItem>) {
+    n.visit_mut_children_with(self);
+    merge_semicolons(n, |x| match x {
+      ModuleItem::Stmt(stmt) => Some(stmt),
+      ModuleItem::ModuleDecl(_) => None,
+    });
+  }
+}
+
+/// Turn `using` statements into `const` ones, which are printed with their
+/// semicolon, and collect their start with whether they are `await using`.
+///
+/// [`UsingWriter`] prints them back as `using` declarations.
+#[derive(Default)]
+pub struct AsConst {
+  pub using: HashMap<BytePos, bool>,
+}
+
+impl VisitMut for AsConst {
+  fn visit_mut_stmt(&mut self, n: &mut Stmt) {
+    n.visit_mut_children_with(self);
+    // the writer finds them by their position
+    let Stmt::Decl(Decl::Using(u)) = n else {
+      return;
+    };
+    if u.span.is_dummy() {
+      return;
+    }
+    self.using.insert(u.span.lo, u.is_await);
+    let var = VarDecl {
+      span: u.span,
+      kind: VarDeclKind::Const,
+      declare: false,
+      decls: std::mem::take(&mut u.decls),
+    };
+    *n = Stmt::Decl(Decl::Var(Box::new(var)));
+  }
+}
+
+/// Writer printing the declarations turned into `const` ones by [`AsConst`]
+/// as `using` declarations.
+pub struct UsingWriter<'a, W> {
+  inner: W,
+  using: &'a HashMap<BytePos, bool>,
+  /// Whether the next keyword starts one of them, and is `await using`.
+  pending: Option<bool>,
+}
+
+impl<'a, W> UsingWriter<'a, W> {
+  pub fn new(inner: W, using: &'a HashMap<BytePos, bool>) -> Self {
+    Self {
+      inner,
+      using,
+      pending: None,
+    }
+  }
+}
+
+impl<W: WriteJs> WriteJs for UsingWriter<'_, W> {
+  fn increase_indent(&mut self) -> Result {
+    self.inner.increase_indent()
+  }
+
+  fn decrease_indent(&mut self) -> Result {
+    self.inner.decrease_indent()
+  }
+
+  fn write_semi(&mut self, span: Option<Span>) -> Result {
+    self.inner.write_semi(span)
+  }
+
+  fn write_space(&mut self) -> Result {
+    self.inner.write_space()
+  }
+
+  fn write_keyword(&mut self, span: Option<Span>, s: &'static str) -> Result {
+    match (self.pending.take(), s) {
+      (Some(is_await), "const") => {
+        if is_await {
+          self.inner.write_keyword(None, "await")?;
+          self.inner.write_space()?;
+        }
+        self.inner.write_keyword(span, "using")
+      }
+      _ => self.inner.write_keyword(span, s),
+    }
+  }
+
+  fn write_operator(&mut self, span: Option<Span>, s: &str) -> Result {
+    self.inner.write_operator(span, s)
+  }
+
+  fn write_param(&mut self, s: &str) -> Result {
+    self.inner.write_param(s)
+  }
+
+  fn write_property(&mut self, s: &str) -> Result {
+    self.inner.write_property(s)
+  }
+
+  fn write_line(&mut self) -> Result {
+    self.inner.write_line()
+  }
+
+  fn write_lit(&mut self, span: Span, s: &str) -> Result {
+    self.inner.write_lit(span, s)
+  }
+
+  fn write_comment(&mut self, s: &str) -> Result {
+    self.inner.write_comment(s)
+  }
+
+  fn write_str_lit(&mut self, span: Span, s: &str) -> Result {
+    self.inner.write_str_lit(span, s)
+  }
+
+  fn write_str(&mut self, s: &str) -> Result {
+    self.inner.write_str(s)
+  }
+
+  fn write_symbol(&mut self, span: Span, s: &str) -> Result {
+    self.inner.write_symbol(span, s)
+  }
+
+  fn write_punct(&mut self, span: Option<Span>, s: &'static str) -> Result {
+    self.inner.write_punct(span, s)
+  }
+
+  fn care_about_srcmap(&self) -> bool {
+    self.inner.care_about_srcmap()
+  }
+
+  fn add_srcmap(&mut self, pos: BytePos) -> Result {
+    // the declaration is mapped right before its keyword
+    if let Some(is_await) = self.using.get(&pos) {
+      self.pending = Some(*is_await);
+    }
+    self.inner.add_srcmap(pos)
+  }
+
+  fn commit_pending_semi(&mut self) -> Result {
+    self.inner.commit_pending_semi()
+  }
+
+  fn can_ignore_invalid_unicodes(&mut self) -> bool {
+    self.inner.can_ignore_invalid_unicodes()
+  }
+}
@@ -1,7 +1,15 @@
+mod commonjs;
+mod context;
 mod count;
+mod define;
 mod import;
+mod local;
+mod pure;
 mod remove;
 
-pub use count::CountVisitor;
-pub use import::ImportVisitor;
-pub use remove::RemoveVisitor;
+pub use context::ResetContext;
+pub use count::{CountVisitor, IdentCounter};
+pub use define::{DefineVisitor, DropSpan};
+pub use import::{ImportFrom, ImportVisitor};
+pub use pure::Annotations;
+pub use remove::{Mode, RemoveVisitor};
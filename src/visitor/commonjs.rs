@@ -0,0 +1,144 @@
+//! CommonJS export and `require()` forms.
+
+use swc_common::SyntaxContext;
+use swc_ecmascript::ast::{
+  AssignOp, AssignTarget, Callee, Expr, Lit, MemberExpr, MemberProp, ObjectLit, Prop, PropName,
+  PropOrSpread, SimpleAssignTarget,
+};
+
+/// An assignment to a CommonJS export.
+pub enum Export<'a> {
+  /// `exports.foo = value`
+  ///
+  /// `Object.defineProperty(exports, "foo", descriptor)`
+  Named(String, &'a Expr),
+  /// `module.exports = { foo, bar }`
+  Object(&'a ObjectLit),
+}
+
+/// The interop flag set by transpiled ES modules, not an export.
+pub const ES_MODULE_FLAG: &str = "__esModule";
+
+/// The global `name`, not shadowed by a binding of the module. `unresolved`
+/// is the context of unresolved identifiers.
+fn is_global(n: &Expr, name: &str, unresolved: SyntaxContext) -> bool {
+  n.as_ident()
+    .is_some_and(|i| &*i.sym == name && i.span.ctxt == unresolved)
+}
+
+fn member_name(n: &MemberExpr) -> Option<String> {
+  match &n.prop {
+    MemberProp::Ident(i) => Some(i.sym.to_string()),
+    MemberProp::Computed(c) => match &*c.expr {
+      Expr::Lit(Lit::Str(s)) => Some(s.value.to_string()),
+      _ => None,
+    },
+    MemberProp::PrivateName(_) => None,
+  }
+}
+
+/// `module.exports`
+fn is_module_exports(n: &MemberExpr, unresolved: SyntaxContext) -> bool {
+  is_global(&n.obj, "module", unresolved) && member_name(n).as_deref() == Some("exports")
+}
+
+/// `exports` or `module.exports`
+fn is_exports(n: &Expr, unresolved: SyntaxContext) -> bool {
+  is_global(n, "exports", unresolved)
+    || n
+      .as_member()
+      .is_some_and(|x| is_module_exports(x, unresolved))
+}
+
+/// Name of a non-computed property key.
+pub fn prop_name(n: &PropName) -> Option<String> {
+  match n {
+    PropName::Ident(i) => Some(i.sym.to_string()),
+    PropName::Str(s) => Some(s.value.to_string()),
+    PropName::Num(n) => Some(n.value.to_string()),
+    PropName::Computed(_) | PropName::BigInt(_) => None,
+  }
+}
+
+/// Name of a property of an object literal, `None` for spreads and computed
+/// keys.
+pub fn prop_or_spread_name(n: &PropOrSpread) -> Option<String> {
+  match n {
+    PropOrSpread::Prop(p) => match &**p {
+      Prop::Shorthand(i) => Some(i.sym.to_string()),
+      Prop::KeyValue(kv) => prop_name(&kv.key),
+      Prop::Method(m) => prop_name(&m.key),
+      Prop::Getter(g) => prop_name(&g.key),
+      Prop::Setter(s) => prop_name(&s.key),
+      Prop::Assign(_) => None,
+    },
+    PropOrSpread::Spread(_) => None,
+  }
+}
+
+/// The export assigned by a top-level expression statement.
+pub fn find_export(n: &Expr, unresolved: SyntaxContext) -> Option<Export<'_>> {
+  match n {
+    Expr::Paren(p) => find_export(&p.expr, unresolved),
+    Expr::Assign(a) if a.op == AssignOp::Assign => {
+      let AssignTarget::Simple(SimpleAssignTarget::Member(left)) = &a.left else {
+        return None;
+      };
+      if is_exports(&left.obj, unresolved) {
+        // exports.foo = ...
+        // module.exports.foo = ...
+        let name = member_name(left).filter(|x| x != ES_MODULE_FLAG)?;
+        return Some(Export::Named(name, &a.right));
+      }
+      match &*a.right {
+        // module.exports = { ... }
+        Expr::Object(o) if is_module_exports(left, unresolved) => Some(Export::Object(o)),
+        _ => None,
+      }
+    }
+    // Object.defineProperty(exports, "foo", { ... })
+    Expr::Call(call) => {
+      let callee = match &call.callee {
+        Callee::Expr(callee) => callee.as_member()?,
+        Callee::Super(_) | Callee::Import(_) => return None,
+      };
+      let is_define = is_global(&callee.obj, "Object", unresolved)
+        && member_name(callee).as_deref() == Some("defineProperty");
+      match &*call.args {
+        [target, name, descriptor] if is_define && is_exports(&target.expr, unresolved) => {
+          // Object.defineProperty(exports, "__esModule", { value: true })
+          match &*name.expr {
+            Expr::Lit(Lit::Str(name)) if &*name.value != ES_MODULE_FLAG => {
+              Some(Export::Named(name.value.to_string(), &descriptor.expr))
+            }
+            _ => None,
+          }
+        }
+        _ => None,
+      }
+    }
+    _ => None,
+  }
+}
+
+/// The object literal of `module.exports = { ... }`.
+pub fn find_exports_object(n: &mut Expr) -> Option<&mut ObjectLit> {
+  match n {
+    Expr::Paren(p) => find_exports_object(&mut p.expr),
+    Expr::Assign(a) => a.right.as_mut_object(),
+    _ => None,
+  }
+}
+
+/// Source module of `require("source")`.
+pub fn find_require(n: &Expr, unresolved: SyntaxContext) -> Option<String> {
+  let call = n.as_call()?;
+  let callee = call.callee.as_expr()?;
+  match &*call.args {
+    [arg] if is_global(callee, "require", unresolved) && arg.spread.is_none() => match &*arg.expr {
+      Expr::Lit(Lit::Str(s)) => Some(s.value.to_string()),
+      _ => None,
+    },
+    _ => None,
+  }
+}
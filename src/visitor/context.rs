@@ -0,0 +1,12 @@
+use swc_common::{Span, SyntaxContext};
+use swc_ecmascript::visit::VisitMut;
+
+/// Forget every syntax context, so that the module can be resolved again
+/// once the marks of its analysis are gone.
+pub struct ResetContext;
+
+impl VisitMut for ResetContext {
+  fn visit_mut_span(&mut self, n: &mut Span) {
+    n.ctxt = SyntaxContext::empty();
+  }
+}
@@ -1,7 +1,7 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use swc_ecmascript::{
-  ast::{Id, Ident},
+  ast::{Id, Ident, Module},
   visit::{Visit, VisitWith},
 };
 
@@ -28,6 +28,34 @@ impl Visit for CountVisitor {
   }
 }
 
+/// Count every occurrence of every id, including declarations.
+#[derive(Default, Debug)]
+pub struct IdentCounter {
+  pub counts: HashMap<Id, usize>,
+  /// Whether `eval` is used.
+  pub eval: bool,
+}
+
+impl IdentCounter {
+  pub fn count(module: &Module) -> Self {
+    let mut counter = Self::default();
+    module.visit_with(&mut counter);
+    counter
+  }
+
+  pub fn is_unused(&self, ident: &Ident) -> bool {
+    self.counts.get(&ident.to_id()).is_some_and(|x| *x <= 1)
+  }
+}
+
+impl Visit for IdentCounter {
+  fn visit_ident(&mut self, n: &Ident) {
+    *self.counts.entry(n.to_id()).or_default() += 1;
+    // direct eval can reference anything
+    self.eval |= &*n.sym == "eval";
+  }
+}
+
 #[cfg(test)]
 mod tests {
   #[test]
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+
+use swc_common::{Span, SyntaxContext, DUMMY_SP};
+use swc_ecmascript::{
+  ast::{
+    BinaryOp, BlockStmt, Bool, EmptyStmt, Expr, Ident, Lit, MemberProp, MetaPropKind, ModuleItem,
+    Stmt, UnaryOp,
+  },
+  visit::{VisitMut, VisitMutWith},
+};
+
+/// Replace global expressions with constants, and drop the branches they
+/// make dead.
+///
+/// `if (import.meta.env.SSR) { ... }` => ``
+pub struct DefineVisitor {
+  /// Replacements by expression, e.g. `import.meta.env.SSR` or
+  /// `typeof window`.
+  pub defines: HashMap<String, Expr>,
+  /// Context of identifiers not declared in the module.
+  pub unresolved: SyntaxContext,
+}
+
+impl DefineVisitor {
+  /// `process.env.NODE_ENV`, `import.meta.env.SSR` or `typeof window`.
+  fn path(&self, n: &Expr) -> Option<String> {
+    match n {
+      Expr::Ident(i) if i.span.ctxt == self.unresolved => Some(i.sym.to_string()),
+      Expr::MetaProp(m) => match m.kind {
+        MetaPropKind::ImportMeta => Some("import.meta".to_string()),
+        MetaPropKind::NewTarget => None,
+      },
+      Expr::Member(m) => {
+        let obj = self.path(&m.obj)?;
+        let prop = match &m.prop {
+          MemberProp::Ident(i) => i.sym.to_string(),
+          MemberProp::Computed(c) => match &*c.expr {
+            Expr::Lit(Lit::Str(s)) => s.value.to_string(),
+            _ => return None,
+          },
+          MemberProp::PrivateName(_) => return None,
+        };
+        Some(format!("{}.{}", obj, prop))
+      }
+      Expr::Unary(u) if u.op == UnaryOp::TypeOf => Some(format!("typeof {}", self.path(&u.arg)?)),
+      Expr::Paren(p) => self.path(&p.expr),
+      _ => None,
+    }
+  }
+
+  fn is_undefined(&self, n: &Ident) -> bool {
+    n.span.ctxt == self.unresolved && &*n.sym == "undefined"
+  }
+
+  /// Truthiness of a constant expression.
+  fn truthiness(&self, n: &Expr) -> Option<bool> {
+    match n {
+      Expr::Lit(Lit::Str(s)) => Some(!s.value.is_empty()),
+      Expr::Lit(Lit::Num(n)) => Some(n.value != 0.0 && !n.value.is_nan()),
+      Expr::Lit(Lit::Bool(b)) => Some(b.value),
+      Expr::Lit(Lit::Null(_)) => Some(false),
+      Expr::Ident(i) if self.is_undefined(i) => Some(false),
+      Expr::Paren(p) => self.truthiness(&p.expr),
+      _ => None,
+    }
+  }
+
+  /// Fold comparisons of constants.
+  fn fold(&self, n: &Expr) -> Option<bool> {
+    match n {
+      Expr::Paren(p) => self.fold(&p.expr),
+      Expr::Unary(u) if u.op == UnaryOp::Bang => self.truthiness(&u.arg).map(|x| !x),
+      Expr::Bin(b) => {
+        let equal = match (&*b.left, &*b.right) {
+          (Expr::Lit(Lit::Str(l)), Expr::Lit(Lit::Str(r))) => l.value == r.value,
+          (Expr::Lit(Lit::Num(l)), Expr::Lit(Lit::Num(r))) => l.value == r.value,
+          (Expr::Lit(Lit::Bool(l)), Expr::Lit(Lit::Bool(r))) => l.value == r.value,
+          (Expr::Lit(Lit::Null(_)), Expr::Lit(Lit::Null(_))) => true,
+          _ => return None,
+        };
+        match b.op {
+          BinaryOp::EqEq | BinaryOp::EqEqEq => Some(equal),
+          BinaryOp::NotEq | BinaryOp::NotEqEq => Some(!equal),
+          _ => None,
+        }
+      }
+      _ => None,
+    }
+  }
+
+  fn condition(&self, n: &Expr) -> Option<bool> {
+    self.truthiness(n).or_else(|| self.fold(n))
+  }
+}
+
+fn bool_expr(value: bool, span: Span) -> Expr {
+  Expr::Lit(Lit::Bool(Bool { span, value }))
+}
+
+/// Placeholder for a dropped statement, removed from statement lists.
+fn removed_stmt() -> Stmt {
+  Stmt::Empty(EmptyStmt { span: DUMMY_SP })
+}
+
+fn is_removed_stmt(n: &Stmt) -> bool {
+  matches!(n, Stmt::Empty(e) if e.span == DUMMY_SP)
+}
+
+impl VisitMut for DefineVisitor {
+  fn visit_mut_expr(&mut self, n: &mut Expr) {
+    if let Some(value) = self.path(n).and_then(|x| self.defines.get(&x)) {
+      *n = value.clone();
+      return;
+    }
+
+    n.visit_mut_children_with(self);
+
+    match n {
+      // "production" === "production"
+      Expr::Bin(b) if matches!(b.op, BinaryOp::LogicalAnd | BinaryOp::LogicalOr) => {
+        let take_left = match (self.condition(&b.left), b.op) {
+          (Some(x), BinaryOp::LogicalAnd) => !x,
+          (Some(x), _) => x,
+          (None, _) => return,
+        };
+        // the left side is a constant, it is dropped if not taken
+        *n = match take_left {
+          true => *b.left.clone(),
+          false => *b.right.clone(),
+        };
+      }
+      Expr::Bin(_) | Expr::Unary(_) => {
+        if let Some(value) = self.fold(n) {
+          let span = match n {
+            Expr::Bin(b) => b.span,
+            Expr::Unary(u) => u.span,
+            _ => unreachable!(),
+          };
+          *n = bool_expr(value, span);
+        }
+      }
+      // SSR ? server() : client()
+      Expr::Cond(c) => match self.condition(&c.test) {
+        Some(true) => *n = *c.cons.clone(),
+        Some(false) => *n = *c.alt.clone(),
+        None => {}
+      },
+      _ => {}
+    }
+  }
+
+  fn visit_mut_stmt(&mut self, n: &mut Stmt) {
+    // "use strict";
+    let constant = matches!(n, Stmt::Expr(e) if self.truthiness(&e.expr).is_some());
+    n.visit_mut_children_with(self);
+
+    // DEV && warn(); => false;
+    if let Stmt::Expr(e) = n {
+      if !constant && self.truthiness(&e.expr).is_some() {
+        *n = removed_stmt();
+      }
+      return;
+    }
+
+    if let Stmt::If(i) = n {
+      *n = match self.condition(&i.test) {
+        Some(true) => *i.cons.clone(),
+        Some(false) => match i.alt.take() {
+          Some(alt) => *alt,
+          None => removed_stmt(),
+        },
+        None => return,
+      };
+      // keep block scoping of the taken branch
+      if let Stmt::Block(BlockStmt { stmts, .. }) = n {
+        if stmts.is_empty() {
+          *n = removed_stmt();
+        }
+      }
+    }
+  }
+
+  fn visit_mut_stmts(&mut self, n: &mut Vec<Stmt>) {
+    n.visit_mut_children_with(self);
+    n.retain(|x| !is_removed_stmt(x));
+  }
+
+  fn visit_mut_module_items(&mut self, n: &mut Vec<ModuleItem>) {
+    n.visit_mut_children_with(self);
+    n.retain(|x| !matches!(x, ModuleItem::Stmt(stmt) if is_removed_stmt(stmt)));
+  }
+}
+
+/// Replace every span with a dummy one.
+pub struct DropSpan;
+
+impl VisitMut for DropSpan {
+  fn visit_mut_span(&mut self, n: &mut Span) {
+    *n = DUMMY_SP;
+  }
+}
@@ -1,16 +1,44 @@
 use std::collections::{HashMap, HashSet};
 
+use swc_common::{Span, Spanned, SyntaxContext};
 use swc_ecmascript::{
   ast::{
-    Decl, DefaultDecl, ExportSpecifier, Id, Ident, ImportSpecifier, ModuleDecl, ModuleExportName,
-    ModuleItem, ObjectPatProp, Pat, Stmt,
+    ArrowExpr, AssignTarget, Decl, DefaultDecl, ExportSpecifier, Expr, ExprStmt, Function, Id,
+    Ident, ImportSpecifier, MemberProp, ModuleDecl, ModuleExportName, ModuleItem, ObjectPatProp,
+    Pat, SimpleAssignTarget, Stmt, TsModuleName, TsModuleRef, VarDecl, VarDeclKind,
   },
-  visit::{noop_visit_type, Visit},
+  visit::{noop_visit_type, Visit, VisitWith},
 };
 
-use super::CountVisitor;
+use super::{commonjs, Annotations, CountVisitor};
 
-#[derive(Default, Debug)]
+/// Where a re-exported name comes from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExportFrom {
+  /// The source module.
+  pub src: String,
+  /// Name exported by the source module, `*` for namespace re-exports.
+  pub orig: String,
+}
+
+/// Where an imported binding comes from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportFrom {
+  /// The source module.
+  pub src: String,
+  /// Name imported from the source module, `default` and `*` for default and
+  /// namespace imports.
+  pub imported: String,
+}
+
+fn module_export_name(n: &ModuleExportName) -> String {
+  match n {
+    ModuleExportName::Ident(i) => i.sym.to_string(),
+    ModuleExportName::Str(s) => s.value.to_string(),
+  }
+}
+
+#[derive(Clone, Default, Debug)]
 pub struct ImportVisitor {
   pub decl_refs: HashMap<Id, HashSet<Id>>,
   pub global_refs: HashSet<Id>,
@@ -24,15 +52,84 @@ pub struct ImportVisitor {
   ///
   /// `export { foo }`
   pub export_refs: HashMap<String, HashSet<Id>>,
+
+  /// Re-exported from another module, can be removed without change anything else.
+  ///
+  /// `export { foo as bar } from "source"`
+  pub export_froms: HashMap<String, ExportFrom>,
+
+  /// Every name of these modules is re-exported.
+  ///
+  /// `export * from "source"`
+  pub export_alls: Vec<String>,
+
+  /// Imported bindings.
+  ///
+  /// `import { foo as bar } from "source"`
+  pub imports: HashMap<Id, ImportFrom>,
+
+  /// `/*#__PURE__*/` annotations of the module.
+  pub annotations: Annotations,
+
+  /// Declarations initialized with pure calls, or only used in branches
+  /// removed by defines, removed if unused.
+  ///
+  /// `const foo = /*#__PURE__*/ createFoo();`
+  pub pure_decls: HashSet<Id>,
+
+  /// Declarations without side effects of their own: imports, functions,
+  /// pure classes and bindings with pure initializers. Only these can be
+  /// removed when defines leave them unused.
+  pub side_effect_free_decls: HashSet<Id>,
+
+  /// Top-level pure calls, always removed.
+  ///
+  /// `/*#__PURE__*/ createFoo();`
+  pub pure_stmts: Vec<(Span, HashSet<Id>)>,
+
+  /// Top-level statements only writing to a module-level binding, removed
+  /// along with it. The binding is not kept alive by them.
+  ///
+  /// `foo.bar = baz;` => `(span, foo, [foo, baz])`
+  pub writes: Vec<(Span, Id, HashSet<Id>)>,
+
+  /// Analyze CommonJS exports and `require()` calls.
+  pub commonjs: bool,
+
+  /// Context of unresolved identifiers, `exports`, `module` and `require`
+  /// must have it to be the CommonJS ones.
+  pub unresolved: SyntaxContext,
+
+  /// Top-level statements assigning a CommonJS export, by export name.
+  ///
+  /// `exports.foo = ...`
+  pub commonjs_exports: HashMap<String, Vec<Span>>,
+
+  /// Top-level `module.exports = { ... }` statements, whose properties are
+  /// exports.
+  pub commonjs_objects: HashSet<Span>,
+
+  /// Analyze top-level statements other than declarations into `stmt_refs`
+  /// instead of `global_refs`.
+  pub prune_statements: bool,
+
+  /// Top-level statements other than declarations, removed along with the
+  /// declarations they use if `prune_statements` is set.
+  ///
+  /// `loader.hydrate = true;`, `if (loader.hydrate) { ... }`
+  pub stmt_refs: Vec<(Span, HashSet<Id>)>,
+
+  /// Remove `using` declarations like other declarations, instead of always
+  /// keeping them for their disposal.
+  pub drop_disposables: bool,
+
+  /// Syntax that cannot be analyzed, the transform should bail out.
+  pub unsupported: Vec<(Span, String)>,
 }
 
 impl ImportVisitor {
   fn insert_decl_refs(&mut self, id: Id, refs: HashSet<Id>) {
-    self
-      .decl_refs
-      .entry(id)
-      .or_insert_with(|| HashSet::new())
-      .extend(refs)
+    self.decl_refs.entry(id).or_default().extend(refs)
   }
 
   fn insert_decls_refs(&mut self, ids: &[Id], refs: &HashSet<Id>) {
@@ -54,19 +151,32 @@ impl ImportVisitor {
   }
 
   fn insert_export_refs(&mut self, name: String, refs: HashSet<Id>) {
-    self
-      .export_refs
-      .entry(name)
-      .or_insert_with(|| HashSet::new())
-      .extend(refs);
+    self.export_refs.entry(name).or_default().extend(refs);
   }
 
   fn insert_export_refs_default(&mut self, refs: HashSet<Id>) {
     self.insert_export_refs("default".to_string(), refs);
   }
 
+  fn insert_export_from(&mut self, name: String, src: String, orig: String) {
+    self.export_froms.insert(name, ExportFrom { src, orig });
+  }
+
+  fn insert_import(&mut self, local: &Ident, src: &str, imported: String) {
+    self.register_decl(local.to_id());
+    self.side_effect_free_decls.insert(local.to_id());
+    let src = src.to_string();
+    self
+      .imports
+      .insert(local.to_id(), ImportFrom { src, imported });
+  }
+
+  fn insert_unsupported(&mut self, span: Span, message: &str) {
+    self.unsupported.push((span, message.to_string()));
+  }
+
   fn register_decl(&mut self, id: Id) {
-    self.decl_refs.entry(id).or_insert_with(|| HashSet::new());
+    self.decl_refs.entry(id).or_default();
   }
 }
 
@@ -91,7 +201,7 @@ impl ImportVisitor {
               let refs = CountVisitor::count(value);
               self.insert_decl_refs(ass.key.to_id(), refs);
             }
-            return vec![ass.key.to_id()];
+            vec![ass.key.to_id()]
           }
           ObjectPatProp::Rest(rest) => self.find_idents(&rest.arg),
         })
@@ -103,78 +213,370 @@ impl ImportVisitor {
         ids
       }
 
-      // ignore
-      Pat::Invalid(_) => panic!("invalid code"),
-      Pat::Expr(_) => panic!("invalid code"),
+      // invalid
+      Pat::Invalid(i) => {
+        self.insert_unsupported(i.span, "invalid binding pattern");
+        vec![]
+      }
+      Pat::Expr(e) => {
+        self.insert_unsupported(e.span(), "expression in binding pattern");
+        vec![]
+      }
     }
   }
 }
 
+impl ImportVisitor {
+  /// Register a module-level declaration and return the ids it creates.
+  fn visit_top_level_decl(&mut self, n: &Decl) -> Vec<Id> {
+    match n {
+      // class foo {}
+      Decl::Class(c) => {
+        let refs = CountVisitor::count(&c.class);
+        self.insert_decl_refs(c.ident.to_id(), refs);
+        if self.annotations.is_pure_class(&c.class) {
+          self.side_effect_free_decls.insert(c.ident.to_id());
+        }
+        vec![c.ident.to_id()]
+      }
+      // function foo() {}
+      // function* foo() {}
+      Decl::Fn(f) => {
+        let refs = CountVisitor::count(&f.function);
+        self.insert_decl_refs(f.ident.to_id(), refs);
+        self.side_effect_free_decls.insert(f.ident.to_id());
+        vec![f.ident.to_id()]
+      }
+      // const foo = ...
+      Decl::Var(v) => {
+        let mut result = vec![];
+        for decl in &v.decls {
+          let ids = self.find_idents(&decl.name);
+          let mut refs = match &decl.init {
+            Some(init) => CountVisitor::count(init),
+            None => HashSet::new(),
+          };
+          // const foo: Foo = ...
+          let type_ann = match &decl.name {
+            Pat::Ident(i) => i.type_ann.as_ref(),
+            Pat::Array(a) => a.type_ann.as_ref(),
+            Pat::Object(o) => o.type_ann.as_ref(),
+            _ => None,
+          };
+          if let Some(type_ann) = type_ann {
+            refs.extend(CountVisitor::count(type_ann));
+          }
+          self.insert_decls_refs(&ids, &refs);
+          if decl
+            .init
+            .as_ref()
+            .is_some_and(|init| self.annotations.is_pure_call(init))
+          {
+            self.pure_decls.extend(ids.iter().cloned());
+          }
+          // const foo = 1;
+          let pure_init = decl.init.as_ref().is_none_or(|init| {
+            self.annotations.is_pure(init) || self.annotations.is_pure_call(init)
+          });
+          if decl.name.is_ident() && pure_init {
+            self.side_effect_free_decls.extend(ids.iter().cloned());
+          }
+          // const foo = require("source");
+          let require = (decl.init.as_deref())
+            .filter(|_| self.commonjs)
+            .and_then(|x| commonjs::find_require(x, self.unresolved));
+          if let Some(src) = require {
+            self.visit_require(&decl.name, &src);
+          }
+          result.extend(ids);
+        }
+        result
+      }
+
+      // interface Foo {}
+      Decl::TsInterface(i) => {
+        let refs = CountVisitor::count(&**i);
+        self.insert_decl_refs(i.id.to_id(), refs);
+        vec![i.id.to_id()]
+      }
+      // type Foo = ...
+      Decl::TsTypeAlias(t) => {
+        let refs = CountVisitor::count(&**t);
+        self.insert_decl_refs(t.id.to_id(), refs);
+        vec![t.id.to_id()]
+      }
+      // enum Foo {}
+      // const enum Foo {}
+      Decl::TsEnum(e) => {
+        let refs = CountVisitor::count(&**e);
+        self.insert_decl_refs(e.id.to_id(), refs);
+        vec![e.id.to_id()]
+      }
+      Decl::TsModule(m) => match &m.id {
+        // namespace Foo {}
+        // declare namespace Foo {}
+        TsModuleName::Ident(i) if !m.global => {
+          let refs = CountVisitor::count(&m.body);
+          self.insert_decl_refs(i.to_id(), refs);
+          vec![i.to_id()]
+        }
+        // declare module "source" {}
+        // declare global {}
+        _ => {
+          self.insert_global_refs(CountVisitor::count(&m.body));
+          vec![]
+        }
+      },
+
+      // using foo = ...
+      // await using foo = ...
+      Decl::Using(u) => {
+        let mut result = vec![];
+        for decl in &u.decls {
+          let ids = self.find_idents(&decl.name);
+          let refs = match &decl.init {
+            Some(init) => CountVisitor::count(init),
+            None => HashSet::new(),
+          };
+          self.insert_decls_refs(&ids, &refs);
+          result.extend(ids);
+        }
+        // disposed when the module is done evaluating, keep them alive
+        if !self.drop_disposables {
+          self.insert_global_refs(result.iter().cloned().collect());
+        }
+        result
+      }
+    }
+  }
+}
+
+impl ImportVisitor {
+  fn visit_top_level_expr(&mut self, n: &ExprStmt) {
+    let refs = CountVisitor::count(n);
+    if self.commonjs {
+      if let Some(export) = commonjs::find_export(&n.expr, self.unresolved) {
+        self.visit_commonjs_export(n.span, export);
+        return;
+      }
+    }
+    if self.annotations.is_pure_call(&n.expr) {
+      // /*#__PURE__*/ foo();
+      self.pure_stmts.push((n.span, refs));
+    } else if let Some(target) = self.find_write(&n.expr) {
+      // foo.bar = baz;
+      self.writes.push((n.span, target.to_id(), refs));
+    } else if self.prune_statements {
+      // foo(bar);
+      self.stmt_refs.push((n.span, refs));
+    } else {
+      self.insert_global_refs(refs);
+    }
+  }
+
+  /// `if (loader.x) { ... }`, `for (...) { ... }`, ...
+  fn visit_top_level_stmt(&mut self, n: &Stmt) {
+    let refs = CountVisitor::count(n);
+    // removing it would also remove the `var` bindings it hoists
+    if self.prune_statements && !VarFinder::find(n) {
+      self.stmt_refs.push((n.span(), refs));
+    } else {
+      self.insert_global_refs(refs);
+    }
+  }
+
+  fn visit_commonjs_export(&mut self, span: Span, n: commonjs::Export) {
+    match n {
+      // exports.foo = ...
+      commonjs::Export::Named(name, value) => {
+        self.insert_export_refs(name.clone(), CountVisitor::count(value));
+        self.commonjs_exports.entry(name).or_default().push(span);
+      }
+      // module.exports = { foo, bar: baz }
+      commonjs::Export::Object(object) => {
+        for prop in &object.props {
+          let refs = CountVisitor::count(prop);
+          match commonjs::prop_or_spread_name(prop) {
+            Some(name) if name != commonjs::ES_MODULE_FLAG => self.insert_export_refs(name, refs),
+            // { __esModule: true, ...foo, [bar]: baz }
+            _ => self.insert_global_refs(refs),
+          }
+        }
+        self.commonjs_objects.insert(span);
+      }
+    }
+  }
+
+  /// `const foo = require("source")` or `const { foo } = require("source")`
+  fn visit_require(&mut self, name: &Pat, src: &str) {
+    match name {
+      Pat::Ident(i) => self.insert_import(&i.id, src, "*".to_string()),
+      Pat::Object(o) => {
+        for prop in &o.props {
+          match prop {
+            ObjectPatProp::KeyValue(kv) => {
+              if let (Some(key), Pat::Ident(i)) = (commonjs::prop_name(&kv.key), &*kv.value) {
+                self.insert_import(&i.id, src, key);
+              }
+            }
+            ObjectPatProp::Assign(a) => {
+              self.insert_import(&a.key.id, src, a.key.sym.to_string());
+            }
+            ObjectPatProp::Rest(_) => {}
+          }
+        }
+      }
+      _ => {}
+    }
+  }
+
+  /// The binding written to by `n`, if that is all it does.
+  ///
+  /// Unless `prune_statements` is set, the written value must be pure.
+  fn find_write<'a>(&self, n: &'a Expr) -> Option<&'a Ident> {
+    let is_pure = |expr: &Expr| self.prune_statements || self.annotations.is_pure(expr);
+    match n {
+      Expr::Paren(p) => self.find_write(&p.expr),
+      // foo = bar
+      // foo.bar = baz
+      Expr::Assign(a) => {
+        let target = match &a.left {
+          AssignTarget::Simple(SimpleAssignTarget::Ident(i)) => &i.id,
+          AssignTarget::Simple(SimpleAssignTarget::Member(m)) => self.member_root(&m.obj)?,
+          _ => return None,
+        };
+        is_pure(&a.right).then_some(target)
+      }
+      // foo++
+      Expr::Update(u) => self.member_root(&u.arg),
+      // Object.assign(foo, { bar })
+      // Object.defineProperty(foo, "bar", { value })
+      Expr::Call(call) => {
+        let member = call.callee.as_expr()?.as_member()?;
+        let object = member.obj.as_ident()?;
+        let method = member.prop.as_ident()?;
+        let writes = matches!(
+          &*method.sym,
+          "assign" | "defineProperty" | "defineProperties"
+        );
+        if &*object.sym != "Object" || !writes {
+          return None;
+        }
+        let (target, values) = call.args.split_first()?;
+        let target = match target.spread {
+          Some(_) => return None,
+          None => self.member_root(&target.expr)?,
+        };
+        let pure = values
+          .iter()
+          .all(|x| x.spread.is_none() && is_pure(&x.expr));
+        pure.then_some(target)
+      }
+      _ => None,
+    }
+  }
+
+  /// `foo` in `foo`, `foo.bar` or `foo["bar"]`.
+  fn member_root<'a>(&self, n: &'a Expr) -> Option<&'a Ident> {
+    match n {
+      Expr::Ident(i) => Some(i),
+      Expr::Paren(p) => self.member_root(&p.expr),
+      Expr::Member(m) => match &m.prop {
+        MemberProp::Computed(c) if !self.annotations.is_pure(&c.expr) => None,
+        _ => self.member_root(&m.obj),
+      },
+      _ => None,
+    }
+  }
+}
+
+/// Find `var` declarations hoisted out of a statement.
+#[derive(Default)]
+struct VarFinder {
+  found: bool,
+}
+
+impl VarFinder {
+  fn find(n: &Stmt) -> bool {
+    let mut finder = Self::default();
+    n.visit_with(&mut finder);
+    finder.found
+  }
+}
+
+impl Visit for VarFinder {
+  noop_visit_type!();
+
+  fn visit_var_decl(&mut self, n: &VarDecl) {
+    self.found |= n.kind == VarDeclKind::Var;
+    n.visit_children_with(self);
+  }
+
+  // their own `var` declarations stay inside them
+  fn visit_function(&mut self, _: &Function) {}
+
+  fn visit_arrow_expr(&mut self, _: &ArrowExpr) {}
+}
+
 impl Visit for ImportVisitor {
   noop_visit_type!();
 
+  fn visit_module_items(&mut self, n: &[ModuleItem]) {
+    self.annotations.find_functions(n);
+    n.visit_children_with(self);
+
+    // writes to globals, or to a shadowed `Object`, are just statements
+    for (span, target, refs) in std::mem::take(&mut self.writes) {
+      let global = refs
+        .iter()
+        .any(|id| &*id.0 == "Object" && self.decl_refs.contains_key(id));
+      if self.decl_refs.contains_key(&target) && !global {
+        self.writes.push((span, target, refs));
+      } else if self.prune_statements {
+        self.stmt_refs.push((span, refs));
+      } else {
+        self.insert_global_refs(refs);
+      }
+    }
+  }
+
   fn visit_module_item(&mut self, n: &ModuleItem) {
     match n {
       ModuleItem::ModuleDecl(decl) => {
         match decl {
           ModuleDecl::Import(decl) => {
+            let src = &decl.src.value;
             for specifier in &decl.specifiers {
               match specifier {
                 // import { a as b } from "..."
                 ImportSpecifier::Named(name) => {
-                  self.register_decl(name.local.to_id());
+                  let imported = match &name.imported {
+                    Some(imported) => module_export_name(imported),
+                    None => name.local.sym.to_string(),
+                  };
+                  self.insert_import(&name.local, src, imported);
                 }
                 // import mod from "..."
                 ImportSpecifier::Default(def) => {
-                  self.register_decl(def.local.to_id());
+                  self.insert_import(&def.local, src, "default".to_string());
                 }
                 // import * as mod from "..."
                 ImportSpecifier::Namespace(ns) => {
-                  self.register_decl(ns.local.to_id());
+                  self.insert_import(&ns.local, src, "*".to_string());
                 }
               }
             }
           }
 
-          ModuleDecl::ExportDecl(decl) => match &decl.decl {
-            // export class foo {}
-            Decl::Class(c) => {
-              let refs = CountVisitor::count(&c.class);
-              self.insert_decl_refs(c.ident.to_id(), refs);
-              self.insert_export_decl_ident(&c.ident);
+          // export const foo = ...
+          // export function foo() {}
+          // export interface Foo {}
+          ModuleDecl::ExportDecl(decl) => {
+            for id in self.visit_top_level_decl(&decl.decl) {
+              let ident = Ident::from(id);
+              self.insert_export_decl_ident(&ident);
             }
-            // export function foo {}
-            // export function* foo {}
-            Decl::Fn(f) => {
-              let refs = CountVisitor::count(&f.function);
-              self.insert_decl_refs(f.ident.to_id(), refs);
-              self.insert_export_decl_ident(&f.ident);
-            }
-            // export const foo = ...
-            Decl::Var(v) => {
-              for decl in &v.decls {
-                let ids = self.find_idents(&decl.name);
-
-                let refs = match &decl.init {
-                  Some(init) => CountVisitor::count(init),
-                  None => HashSet::new(),
-                };
-                self.insert_decls_refs(&ids, &refs);
-
-                for id in ids {
-                  let ident = Ident::from(id);
-                  self.insert_export_decl_ident(&ident);
-                }
-              }
-            }
-
-            // invalid
-            Decl::Using(_) => panic!("invalid code"),
-            Decl::TsInterface(_) => panic!("invalid code"),
-            Decl::TsTypeAlias(_) => panic!("invalid code"),
-            Decl::TsEnum(_) => panic!("invalid code"),
-            Decl::TsModule(_) => panic!("invalid code"),
-          },
+          }
 
           ModuleDecl::ExportDefaultDecl(decl) => match &decl.decl {
             // export default class {}
@@ -197,8 +599,11 @@ impl Visit for ImportVisitor {
               self.insert_export_refs_default(refs);
             }
 
-            // invalid
-            DefaultDecl::TsInterfaceDecl(_) => panic!("invalid code"),
+            // export default interface Foo {}
+            DefaultDecl::TsInterfaceDecl(i) => {
+              let refs = CountVisitor::count(&**i);
+              self.insert_export_refs_default(refs);
+            }
           },
 
           // export default foo;
@@ -208,8 +613,9 @@ impl Visit for ImportVisitor {
           }
 
           // export * from "source";
-          // do nothing;
-          ModuleDecl::ExportAll(_) => {}
+          ModuleDecl::ExportAll(all) => {
+            self.export_alls.push(all.src.value.to_string());
+          }
 
           ModuleDecl::ExportNamed(name) => {
             // export { foo, bar as foo };
@@ -219,14 +625,16 @@ impl Visit for ImportVisitor {
                   ExportSpecifier::Named(name) => {
                     let ident = match &name.orig {
                       ModuleExportName::Ident(i) => i,
-                      ModuleExportName::Str(_) => panic!("invalid code"),
+                      ModuleExportName::Str(s) => {
+                        self.insert_unsupported(s.span, "string local name in export");
+                        continue;
+                      }
                     };
 
                     let exported_name = match &name.exported {
                       // export { foo as bar }
-                      Some(ModuleExportName::Ident(i)) => i.sym.to_string(),
                       // export { foo as "bar" }
-                      Some(ModuleExportName::Str(s)) => s.value.to_string(),
+                      Some(exported) => module_export_name(exported),
                       // export { foo }
                       None => ident.sym.to_string(),
                     };
@@ -235,71 +643,88 @@ impl Visit for ImportVisitor {
                   }
 
                   // invalid
-                  ExportSpecifier::Namespace(_) => panic!("invalid code"),
-                  ExportSpecifier::Default(_) => panic!("invalid code"),
+                  ExportSpecifier::Namespace(ns) => {
+                    self.insert_unsupported(ns.span, "namespace export without source");
+                  }
+                  ExportSpecifier::Default(def) => {
+                    self.insert_unsupported(def.exported.span, "default re-export");
+                  }
                 }
               }
             }
 
-            // export { foo } from "source";
-            // just ignore
+            // export { foo, bar as baz } from "source";
+            // export * as foo from "source";
+            if let Some(src) = &name.src {
+              for specifier in &name.specifiers {
+                match specifier {
+                  ExportSpecifier::Named(named) => {
+                    let orig = module_export_name(&named.orig);
+                    let exported = match &named.exported {
+                      Some(exported) => module_export_name(exported),
+                      None => orig.clone(),
+                    };
+                    self.insert_export_from(exported, src.value.to_string(), orig);
+                  }
+                  ExportSpecifier::Namespace(ns) => {
+                    let exported = module_export_name(&ns.name);
+                    self.insert_export_from(exported, src.value.to_string(), "*".to_string());
+                  }
+                  ExportSpecifier::Default(def) => {
+                    self.insert_unsupported(def.exported.span, "default re-export");
+                  }
+                }
+              }
+            }
+          }
+
+          // import foo = require("source");
+          // import foo = Bar.Baz;
+          // export import foo = Bar.Baz;
+          ModuleDecl::TsImportEquals(decl) => {
+            let refs = CountVisitor::count(&decl.module_ref);
+            self.insert_decl_refs(decl.id.to_id(), refs);
+            if let TsModuleRef::TsExternalModuleRef(module) = &decl.module_ref {
+              self.insert_import(&decl.id, &module.expr.value, "*".to_string());
+            }
+            if decl.is_export {
+              self.insert_export_decl_ident(&decl.id);
+            }
+          }
+
+          // export = foo;
+          ModuleDecl::TsExportAssignment(assign) => {
+            self.insert_global_refs(CountVisitor::count(&assign.expr));
           }
 
-          // invalid
-          ModuleDecl::TsImportEquals(_) => {}
-          ModuleDecl::TsExportAssignment(_) => {}
+          // export as namespace Foo;
+          // do nothing;
           ModuleDecl::TsNamespaceExport(_) => {}
         }
       }
 
       ModuleItem::Stmt(stmt) => match stmt {
         Stmt::Decl(decl) => {
-          match decl {
-            Decl::Class(c) => {
-              let refs = CountVisitor::count(&c.class);
-              self.insert_decl_refs(c.ident.to_id(), refs);
-            }
-            Decl::Fn(f) => {
-              let refs = CountVisitor::count(&f.function);
-              self.insert_decl_refs(f.ident.to_id(), refs);
-            }
-            Decl::Var(v) => {
-              for decl in &v.decls {
-                let ids = self.find_idents(&decl.name);
-                let refs = match &decl.init {
-                  Some(init) => CountVisitor::count(init),
-                  None => HashSet::new(),
-                };
-                self.insert_decls_refs(&ids, &refs);
-              }
-            }
-
-            // invalid
-            Decl::Using(_) => panic!("invalid code"),
-            Decl::TsInterface(_) => panic!("invalid code"),
-            Decl::TsTypeAlias(_) => panic!("invalid code"),
-            Decl::TsEnum(_) => panic!("invalid code"),
-            Decl::TsModule(_) => panic!("invalid code"),
-          }
+          self.visit_top_level_decl(decl);
         }
-        Stmt::Block(x) => self.insert_global_refs(CountVisitor::count(x)),
-        Stmt::Empty(x) => self.insert_global_refs(CountVisitor::count(x)),
-        Stmt::Debugger(x) => self.insert_global_refs(CountVisitor::count(x)),
-        Stmt::With(x) => self.insert_global_refs(CountVisitor::count(x)),
-        Stmt::Return(x) => self.insert_global_refs(CountVisitor::count(x)),
-        Stmt::Labeled(x) => self.insert_global_refs(CountVisitor::count(x)),
-        Stmt::Break(x) => self.insert_global_refs(CountVisitor::count(x)),
-        Stmt::Continue(x) => self.insert_global_refs(CountVisitor::count(x)),
-        Stmt::If(x) => self.insert_global_refs(CountVisitor::count(x)),
-        Stmt::Switch(x) => self.insert_global_refs(CountVisitor::count(x)),
-        Stmt::Throw(x) => self.insert_global_refs(CountVisitor::count(x)),
-        Stmt::Try(x) => self.insert_global_refs(CountVisitor::count(x)),
-        Stmt::While(x) => self.insert_global_refs(CountVisitor::count(x)),
-        Stmt::DoWhile(x) => self.insert_global_refs(CountVisitor::count(x)),
-        Stmt::For(x) => self.insert_global_refs(CountVisitor::count(x)),
-        Stmt::ForIn(x) => self.insert_global_refs(CountVisitor::count(x)),
-        Stmt::ForOf(x) => self.insert_global_refs(CountVisitor::count(x)),
-        Stmt::Expr(x) => self.insert_global_refs(CountVisitor::count(x)),
+        Stmt::Block(_)
+        | Stmt::Empty(_)
+        | Stmt::Debugger(_)
+        | Stmt::With(_)
+        | Stmt::Return(_)
+        | Stmt::Labeled(_)
+        | Stmt::Break(_)
+        | Stmt::Continue(_)
+        | Stmt::If(_)
+        | Stmt::Switch(_)
+        | Stmt::Throw(_)
+        | Stmt::Try(_)
+        | Stmt::While(_)
+        | Stmt::DoWhile(_)
+        | Stmt::For(_)
+        | Stmt::ForIn(_)
+        | Stmt::ForOf(_) => self.visit_top_level_stmt(stmt),
+        Stmt::Expr(x) => self.visit_top_level_expr(x),
       },
     }
   }
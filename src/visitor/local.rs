@@ -0,0 +1,104 @@
+use swc_ecmascript::{
+  ast::{Decl, ImportSpecifier, Module, ModuleDecl, ModuleItem, Pat, Stmt, VarDeclarator},
+  visit::{VisitMut, VisitMutWith},
+};
+
+use super::{Annotations, IdentCounter, RemoveVisitor};
+
+/// Remove unused side-effect free declarations in nested scopes.
+struct LocalVisitor<'a> {
+  counter: &'a IdentCounter,
+  annotations: &'a Annotations,
+  changed: bool,
+}
+
+impl LocalVisitor<'_> {
+  fn is_unused_declarator(&self, n: &VarDeclarator) -> bool {
+    let Pat::Ident(ident) = &n.name else {
+      return false;
+    };
+    self.counter.is_unused(&ident.id) && n.init.as_ref().is_none_or(|x| self.annotations.is_pure(x))
+  }
+
+  fn should_remove_stmt(&mut self, n: &mut Stmt) -> bool {
+    let Stmt::Decl(decl) = n else {
+      return false;
+    };
+    match decl {
+      // function foo() {}
+      Decl::Fn(f) => self.counter.is_unused(&f.ident),
+      // class Foo {}
+      Decl::Class(c) => {
+        self.counter.is_unused(&c.ident) && self.annotations.is_pure_class(&c.class)
+      }
+      // const foo = 1, bar = 2;
+      Decl::Var(v) => {
+        let len = v.decls.len();
+        v.decls.retain(|x| !self.is_unused_declarator(x));
+        self.changed |= v.decls.len() != len;
+        v.decls.is_empty()
+      }
+      // interface Foo {}
+      Decl::TsInterface(i) => self.counter.is_unused(&i.id),
+      // type Foo = ...
+      Decl::TsTypeAlias(t) => self.counter.is_unused(&t.id),
+      Decl::TsEnum(_) | Decl::TsModule(_) | Decl::Using(_) => false,
+    }
+  }
+}
+
+impl VisitMut for LocalVisitor<'_> {
+  fn visit_mut_stmts(&mut self, n: &mut Vec<Stmt>) {
+    n.retain_mut(|x| {
+      let remove = self.should_remove_stmt(x);
+      self.changed |= remove;
+      !remove
+    });
+    n.visit_mut_children_with(self);
+  }
+}
+
+impl RemoveVisitor {
+  /// Remove unused side-effect free declarations inside kept code, and the
+  /// imports only they used.
+  pub fn prune_locals(&mut self, module: &mut Module) {
+    let before = IdentCounter::count(module);
+    if before.eval {
+      return;
+    }
+
+    let mut after = IdentCounter::count(module);
+    loop {
+      let mut local = LocalVisitor {
+        counter: &after,
+        annotations: &self.annotations,
+        changed: false,
+      };
+      module.visit_mut_with(&mut local);
+      if !local.changed {
+        break;
+      }
+      after = IdentCounter::count(module);
+    }
+
+    // imports that were used before, but not anymore
+    for item in &module.body {
+      if let ModuleItem::ModuleDecl(ModuleDecl::Import(import)) = item {
+        for specifier in &import.specifiers {
+          let local = match specifier {
+            ImportSpecifier::Named(x) => &x.local,
+            ImportSpecifier::Default(x) => &x.local,
+            ImportSpecifier::Namespace(x) => &x.local,
+          };
+          if !before.is_unused(local) && after.is_unused(local) {
+            self.ids.insert(local.to_id());
+          }
+        }
+      }
+    }
+    module.body.retain_mut(|x| match x {
+      ModuleItem::ModuleDecl(decl @ ModuleDecl::Import(_)) => !self.should_remove_module_decl(decl),
+      _ => true,
+    });
+  }
+}
@@ -0,0 +1,154 @@
+use std::collections::HashSet;
+
+use swc_common::{
+  comments::{Comments, SingleThreadedComments},
+  BytePos, Spanned,
+};
+use swc_ecmascript::ast::{
+  Callee, Class, ClassMember, Decl, Expr, ExprOrSpread, Id, ModuleDecl, ModuleItem, Prop,
+  PropOrSpread, Stmt,
+};
+
+/// Positions of `/*#__PURE__*/` and `/*#__NO_SIDE_EFFECTS__*/` annotations.
+#[derive(Clone, Default, Debug)]
+pub struct Annotations {
+  pure: HashSet<BytePos>,
+  no_side_effects: HashSet<BytePos>,
+  /// Functions annotated with `/*#__NO_SIDE_EFFECTS__*/`, calls to them are
+  /// pure.
+  functions: HashSet<Id>,
+}
+
+impl Annotations {
+  pub fn new(comments: &SingleThreadedComments) -> Self {
+    let positions = comments.borrow_all().0.keys().copied().collect::<Vec<_>>();
+    let mut annotations = Self::default();
+    for pos in positions {
+      if comments.has_flag(pos, "PURE") {
+        annotations.pure.insert(pos);
+      }
+      if comments.has_flag(pos, "NO_SIDE_EFFECTS") {
+        annotations.no_side_effects.insert(pos);
+      }
+    }
+    annotations
+  }
+
+  fn has_no_side_effects(&self, lo: &[BytePos]) -> bool {
+    lo.iter().any(|x| self.no_side_effects.contains(x))
+  }
+
+  /// Find the module-level functions annotated with `/*#__NO_SIDE_EFFECTS__*/`.
+  pub fn find_functions(&mut self, items: &[ModuleItem]) {
+    for item in items {
+      let (lo, decl) = match item {
+        // export /*#__NO_SIDE_EFFECTS__*/ function foo() {}
+        // /*#__NO_SIDE_EFFECTS__*/ export function foo() {}
+        ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(e)) => (e.span.lo, &e.decl),
+        ModuleItem::Stmt(Stmt::Decl(decl)) => (decl.span().lo, decl),
+        _ => continue,
+      };
+      match decl {
+        // /*#__NO_SIDE_EFFECTS__*/ function foo() {}
+        Decl::Fn(f) if self.has_no_side_effects(&[lo, f.function.span.lo]) => {
+          self.functions.insert(f.ident.to_id());
+        }
+        // /*#__NO_SIDE_EFFECTS__*/ const foo = () => {}
+        // const foo = /*#__NO_SIDE_EFFECTS__*/ () => {}
+        Decl::Var(v) => {
+          for decl in &v.decls {
+            let (Some(ident), Some(init)) = (decl.name.as_ident(), &decl.init) else {
+              continue;
+            };
+            let is_fn = matches!(&**init, Expr::Arrow(_) | Expr::Fn(_));
+            if is_fn && self.has_no_side_effects(&[lo, v.span.lo, init.span().lo]) {
+              self.functions.insert(ident.to_id());
+            }
+          }
+        }
+        _ => {}
+      }
+    }
+  }
+
+  /// Whether `expr` is an annotated call that can be dropped if unused.
+  pub fn is_pure_call(&self, expr: &Expr) -> bool {
+    match expr {
+      Expr::Paren(p) => self.is_pure_call(&p.expr),
+      // /*#__PURE__*/ foo(...)
+      // foo(...) where foo is /*#__NO_SIDE_EFFECTS__*/
+      Expr::Call(call) => {
+        let callee = match &call.callee {
+          Callee::Expr(callee) => callee,
+          Callee::Super(_) | Callee::Import(_) => return false,
+        };
+        let annotated = self.pure.contains(&call.span.lo)
+          || callee
+            .as_ident()
+            .is_some_and(|i| self.functions.contains(&i.to_id()));
+        annotated && self.is_pure(callee) && self.are_pure(&call.args)
+      }
+      // /*#__PURE__*/ new Foo(...)
+      Expr::New(new) => {
+        self.pure.contains(&new.span.lo)
+          && self.is_pure(&new.callee)
+          && new.args.as_ref().is_none_or(|args| self.are_pure(args))
+      }
+      _ => false,
+    }
+  }
+
+  fn are_pure(&self, args: &[ExprOrSpread]) -> bool {
+    args
+      .iter()
+      .all(|arg| arg.spread.is_none() && self.is_pure(&arg.expr))
+  }
+
+  /// Whether defining the class `n` has no side effects.
+  pub fn is_pure_class(&self, n: &Class) -> bool {
+    n.decorators.is_empty()
+      && n.super_class.as_ref().is_none_or(|x| self.is_pure(x))
+      && n.body.iter().all(|x| match x {
+        ClassMember::Constructor(_) | ClassMember::TsIndexSignature(_) | ClassMember::Empty(_) => {
+          true
+        }
+        ClassMember::Method(m) => !m.key.is_computed(),
+        ClassMember::PrivateMethod(_) => true,
+        ClassMember::ClassProp(p) => {
+          !p.key.is_computed() && !p.is_static && p.decorators.is_empty()
+        }
+        ClassMember::PrivateProp(p) => !p.is_static && p.decorators.is_empty(),
+        ClassMember::StaticBlock(_) | ClassMember::AutoAccessor(_) => false,
+      })
+  }
+
+  /// Whether evaluating `expr` has no side effects.
+  pub fn is_pure(&self, expr: &Expr) -> bool {
+    match expr {
+      Expr::Lit(_) | Expr::Ident(_) | Expr::Arrow(_) | Expr::Fn(_) => true,
+      Expr::Paren(p) => self.is_pure(&p.expr),
+      Expr::Seq(s) => s.exprs.iter().all(|x| self.is_pure(x)),
+      Expr::Tpl(t) => t.exprs.iter().all(|x| self.is_pure(x)),
+      Expr::Array(a) => a.elems.iter().flatten().all(|x| {
+        // [...foo] runs the iterator
+        x.spread.is_none() && self.is_pure(&x.expr)
+      }),
+      Expr::Object(o) => o.props.iter().all(|x| match x {
+        PropOrSpread::Prop(p) => match &**p {
+          Prop::Shorthand(_) => true,
+          Prop::KeyValue(kv) => !kv.key.is_computed() && self.is_pure(&kv.value),
+          Prop::Method(m) => !m.key.is_computed(),
+          Prop::Getter(_) | Prop::Setter(_) | Prop::Assign(_) => false,
+        },
+        PropOrSpread::Spread(_) => false,
+      }),
+      Expr::TsAs(e) => self.is_pure(&e.expr),
+      Expr::TsSatisfies(e) => self.is_pure(&e.expr),
+      Expr::TsConstAssertion(e) => self.is_pure(&e.expr),
+      Expr::TsNonNull(e) => self.is_pure(&e.expr),
+      Expr::TsTypeAssertion(e) => self.is_pure(&e.expr),
+      Expr::Call(_) | Expr::New(_) => self.is_pure_call(expr),
+      _ => false,
+    }
+  }
+}
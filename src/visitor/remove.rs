@@ -1,23 +1,67 @@
 use std::{
-  collections::{HashMap, HashSet, VecDeque},
-  fmt::Debug,
+  collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
   hash::Hash,
 };
 
+use swc_common::{Span, Spanned, DUMMY_SP};
 use swc_ecmascript::{
   ast::{
-    Decl, ExportSpecifier, Id, Ident, ImportSpecifier, ModuleDecl, ModuleExportName, ModuleItem,
-    ObjectPatProp, Pat, Stmt,
+    Decl, ExportNamedSpecifier, ExportSpecifier, ExprStmt, Id, Ident, ImportSpecifier, ModuleDecl,
+    ModuleExportName, ModuleItem, NamedExport, ObjectPatProp, Pat, Stmt, TsModuleName,
+    VarDeclarator,
   },
   visit::{noop_visit_mut_type, VisitMut},
 };
 
-use super::ImportVisitor;
+use serde::Deserialize;
+
+use super::{commonjs, Annotations, CountVisitor, ImportFrom, ImportVisitor};
+use crate::Matcher;
+
+/// How the exports matched by the [`Matcher`] passed to [`RemoveVisitor::new`] are used.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+  /// Remove the given exports.
+  #[default]
+  Remove,
+  /// Keep the given exports and remove every other one.
+  Keep,
+}
 
 #[derive(Debug)]
 pub struct RemoveVisitor {
+  mode: Mode,
   pub names: HashSet<String>,
   pub ids: HashSet<Id>,
+  /// Top-level statements other than declarations to remove.
+  pub stmts: HashSet<Span>,
+  /// `module.exports = { ... }` statements.
+  pub commonjs_objects: HashSet<Span>,
+  /// Imported bindings, including `require()` ones.
+  pub import_froms: HashMap<Id, ImportFrom>,
+  /// Removed bindings kept in front of an object rest pattern, in the
+  /// current module item.
+  placeholders: Vec<Id>,
+  /// Modules whose emptied imports are kept as `import "source"`.
+  pub side_effects: Matcher,
+  /// Modules whose emptied imports are always dropped.
+  pub side_effect_free: Matcher,
+  /// `/*#__PURE__*/` annotations of the module.
+  pub annotations: Annotations,
+
+  /// Export names that were found and removed.
+  pub removed_exports: BTreeSet<String>,
+  /// Requested export names that were not found.
+  pub not_found: BTreeSet<String>,
+  /// Module-level declarations that were removed.
+  pub removed_decls: BTreeSet<String>,
+  /// Import specifiers that were removed, by source module.
+  ///
+  /// `import { foo as bar } from "source"` => `"source": ["foo"]`
+  pub removed_imports: BTreeMap<String, BTreeSet<String>>,
+  /// Module items, declarators and specifiers that were removed.
+  pub removed_spans: Vec<Span>,
 }
 
 impl RemoveVisitor {
@@ -25,6 +69,42 @@ impl RemoveVisitor {
     self.ids.contains(&ident.to_id())
   }
 
+  /// Same as `should_remove_ident`, but also records the removed declaration.
+  fn should_remove_decl_ident(&mut self, ident: &Ident) -> bool {
+    let remove = self.should_remove_ident(ident);
+    match self.import_froms.get(&ident.to_id()) {
+      // const foo = require("source");
+      Some(import) if remove => {
+        let (src, imported) = (import.src.clone(), import.imported.clone());
+        self
+          .removed_imports
+          .entry(src)
+          .or_default()
+          .insert(imported);
+      }
+      _ if remove => {
+        self.removed_decls.insert(ident.sym.to_string());
+      }
+      _ => {}
+    }
+    remove
+  }
+
+  fn should_remove_expr_stmt(&mut self, n: &mut ExprStmt) -> bool {
+    if self.stmts.contains(&n.span) {
+      return true;
+    }
+    // module.exports = { foo, bar };
+    if self.commonjs_objects.contains(&n.span) {
+      if let Some(object) = commonjs::find_exports_object(&mut n.expr) {
+        object.props.retain(|x| {
+          commonjs::prop_or_spread_name(x).is_none_or(|name| !self.names.contains(&name))
+        });
+      }
+    }
+    false
+  }
+
   fn should_remove_module_export(&self, n: &ModuleExportName) -> bool {
     self.names.contains(&match n {
       ModuleExportName::Ident(ident) => ident.sym.to_string(),
@@ -32,10 +112,26 @@ impl RemoveVisitor {
     })
   }
 
+  fn should_remove_import(&mut self, src: &str, imported: String, local: &Ident) -> bool {
+    let remove = self.should_remove_ident(local);
+    if remove {
+      self
+        .removed_imports
+        .entry(src.to_string())
+        .or_default()
+        .insert(imported);
+    }
+    remove
+  }
+
+  fn has_side_effects(&self, src: &str) -> bool {
+    self.side_effects.is_match(src) && !self.side_effect_free.is_match(src)
+  }
+
   fn should_remove_pat(&mut self, n: &mut Pat) -> bool {
     match n {
       // foo
-      Pat::Ident(i) => self.should_remove_ident(&i.id),
+      Pat::Ident(i) => self.should_remove_decl_ident(&i.id),
       // [ foo, bar ]
       Pat::Array(a) => {
         a.elems.iter_mut().for_each(|x| {
@@ -47,13 +143,47 @@ impl RemoveVisitor {
       }
       // { foo, bar }
       Pat::Object(o) => {
+        // { foo, ...rest }
+        // removing `foo` would add it to `rest`, keep it as an unused binding
+        let has_rest = match o.props.last_mut() {
+          Some(ObjectPatProp::Rest(rs)) => !self.should_remove_pat(&mut rs.arg),
+          _ => false,
+        };
         o.props.retain_mut(|i| match i {
           // { key: value }
-          ObjectPatProp::KeyValue(kv) => !self.should_remove_pat(&mut kv.value),
+          ObjectPatProp::KeyValue(kv) => {
+            let idents = binding_idents(&kv.value);
+            if !self.should_remove_pat(&mut kv.value) {
+              return true;
+            }
+            // { key: { foo }, ...rest }
+            if has_rest && self.keep_pat_shape(&mut kv.value) {
+              return true;
+            }
+            match idents.into_iter().next() {
+              // { key: foo, ...rest }
+              Some(ident) if has_rest => {
+                self.placeholders.push(ident.to_id());
+                *kv.value = Pat::Ident(ident.into());
+                true
+              }
+              _ => false,
+            }
+          }
           // { foo = 233 }
-          ObjectPatProp::Assign(a) => !self.should_remove_ident(&a.key.id),
+          ObjectPatProp::Assign(a) => {
+            if !self.should_remove_decl_ident(&a.key.id) {
+              return true;
+            }
+            if has_rest {
+              // { foo, ...rest }
+              self.placeholders.push(a.key.to_id());
+              a.value = None;
+            }
+            has_rest
+          }
           // { ...rest }
-          ObjectPatProp::Rest(rs) => !self.should_remove_pat(&mut rs.arg),
+          ObjectPatProp::Rest(_) => has_rest,
         });
         o.props.is_empty()
       }
@@ -61,60 +191,107 @@ impl RemoveVisitor {
       Pat::Rest(rs) => self.should_remove_pat(&mut rs.arg),
       // [ foo = 233 ]
       Pat::Assign(a) => self.should_remove_pat(&mut a.left),
-      // ???
-      Pat::Expr(_) => panic!("invalid code"),
-      Pat::Invalid(_) => panic!("invalid code"),
+      // rejected by ImportVisitor
+      Pat::Expr(_) => unreachable!("invalid code"),
+      Pat::Invalid(_) => unreachable!("invalid code"),
     }
   }
 
-  fn should_remove_module_decl(&mut self, n: &mut ModuleDecl) -> bool {
+  /// Keep an emptied nested pattern as `{}` or `[]`, which still throws
+  /// when its value cannot be destructured, returns whether it can be kept.
+  fn keep_pat_shape(&self, n: &mut Pat) -> bool {
     match n {
-      ModuleDecl::ExportDecl(decl) => {
-        match &mut decl.decl {
-          // export class foo { }
-          Decl::Class(c) => self.should_remove_ident(&c.ident),
-
-          // export function foo() { }
-          Decl::Fn(f) => self.should_remove_ident(&f.ident),
-
-          // export const foo = ...
-          // export let foo = ...
-          // export var foo = ...
-          Decl::Var(v) => {
-            v.decls
-              .retain_mut(|decl| !self.should_remove_pat(&mut decl.name));
-            v.decls.is_empty()
-          }
+      Pat::Object(_) => true,
+      // [ , , ]
+      Pat::Array(a) => {
+        a.elems.clear();
+        true
+      }
+      // { key: { foo } = {} }, unless the default uses removed declarations
+      Pat::Assign(a) => {
+        let refs = CountVisitor::count(&a.right);
+        !refs.iter().any(|id| self.ids.contains(id)) && self.keep_pat_shape(&mut a.left)
+      }
+      _ => false,
+    }
+  }
 
-          // export enum Foo { }
-          Decl::TsEnum(_) => panic!("invalid code"),
-          // export using foo = ...
-          Decl::Using(_) => panic!("invalid code"),
-          // export interface Foo {}
-          Decl::TsInterface(_) => panic!("invalid code"),
-          // export type Foo = ...
-          Decl::TsTypeAlias(_) => panic!("invalid code"),
-          // export declare module "xxx" { }
-          Decl::TsModule(_) => panic!("invalid code"),
-        }
+  /// Remove the declarators binding removed declarations, returns whether
+  /// none are left.
+  fn should_remove_declarators(&mut self, n: &mut Vec<VarDeclarator>) -> bool {
+    n.retain_mut(|decl| {
+      let remove = self.should_remove_pat(&mut decl.name);
+      if remove {
+        self.removed_spans.push(decl.span);
       }
+      !remove
+    });
+    n.is_empty()
+  }
+
+  fn should_remove_decl(&mut self, n: &mut Decl) -> bool {
+    match n {
+      // class foo { }
+      Decl::Class(c) => self.should_remove_decl_ident(&c.ident),
+
+      // function foo() { }
+      Decl::Fn(f) => self.should_remove_decl_ident(&f.ident),
+
+      // const foo = ...
+      // let foo = ...
+      // var foo = ...
+      Decl::Var(v) => self.should_remove_declarators(&mut v.decls),
+
+      // enum Foo { }
+      Decl::TsEnum(e) => self.should_remove_decl_ident(&e.id),
+      // interface Foo {}
+      Decl::TsInterface(i) => self.should_remove_decl_ident(&i.id),
+      // type Foo = ...
+      Decl::TsTypeAlias(t) => self.should_remove_decl_ident(&t.id),
+      // namespace Foo { }
+      // declare module "xxx" { }
+      Decl::TsModule(m) => match &m.id {
+        TsModuleName::Ident(i) => self.should_remove_decl_ident(i),
+        TsModuleName::Str(_) => false,
+      },
+
+      // using foo = ...
+      // await using foo = ...
+      // only removable with `drop_disposables`
+      Decl::Using(u) => self.should_remove_declarators(&mut u.decls),
+    }
+  }
+
+  pub(super) fn should_remove_module_decl(&mut self, n: &mut ModuleDecl) -> bool {
+    match n {
+      // export class foo { }
+      // export function foo() { }
+      // export const foo = ...
+      ModuleDecl::ExportDecl(decl) => self.should_remove_decl(&mut decl.decl),
 
       ModuleDecl::ExportNamed(named) => {
-        named.specifiers.retain(|exp| match exp {
-          // export * as foo from "source"
-          ExportSpecifier::Namespace(namespace) => {
-            !self.should_remove_module_export(&namespace.name)
-          }
-          // export { name, foo as bar };
-          // export { name, foo as bar } from "source";
-          ExportSpecifier::Named(named) => {
-            !self.should_remove_module_export(match &named.exported {
-              Some(exported) => exported,
-              None => &named.orig,
-            })
+        named.specifiers.retain(|exp| {
+          let keep = match exp {
+            // export * as foo from "source"
+            ExportSpecifier::Namespace(namespace) => {
+              !self.should_remove_module_export(&namespace.name)
+            }
+            // export { name, foo as bar };
+            // export { name, foo as bar } from "source";
+            ExportSpecifier::Named(named) => {
+              !self.should_remove_module_export(match &named.exported {
+                Some(exported) => exported,
+                None => &named.orig,
+              })
+            }
+            // export v from "source";
+            // `export_default_from` is never enabled in the parser
+            ExportSpecifier::Default(_) => unreachable!("invalid code"),
+          };
+          if !keep {
+            self.removed_spans.push(exp.span());
           }
-          // export v from "source";
-          ExportSpecifier::Default(_) => panic!("invalid code"),
+          keep
         });
         named.specifiers.is_empty()
       }
@@ -128,28 +305,49 @@ impl RemoveVisitor {
       // import "source";
       // import { ... } from "source";
       ModuleDecl::Import(import) => {
+        let src = import.src.value.to_string();
         let old = import.specifiers.len();
-        import.specifiers.retain(|x| match x {
-          // import { foo, foo as bar } from "source";
-          ImportSpecifier::Named(name) => !self.should_remove_ident(&name.local),
-          // import foo from "source";
-          ImportSpecifier::Default(def) => !self.should_remove_ident(&def.local),
-          // import * as foo from "source";
-          ImportSpecifier::Namespace(ns) => !self.should_remove_ident(&ns.local),
+        import.specifiers.retain(|x| {
+          let keep = match x {
+            // import { foo, foo as bar } from "source";
+            ImportSpecifier::Named(name) => {
+              let imported = match &name.imported {
+                Some(ModuleExportName::Ident(i)) => i.sym.to_string(),
+                Some(ModuleExportName::Str(s)) => s.value.to_string(),
+                None => name.local.sym.to_string(),
+              };
+              !self.should_remove_import(&src, imported, &name.local)
+            }
+            // import foo from "source";
+            ImportSpecifier::Default(def) => {
+              !self.should_remove_import(&src, "default".to_string(), &def.local)
+            }
+            // import * as foo from "source";
+            ImportSpecifier::Namespace(ns) => {
+              !self.should_remove_import(&src, "*".to_string(), &ns.local)
+            }
+          };
+          if !keep {
+            self.removed_spans.push(x.span());
+          }
+          keep
         });
         let now = import.specifiers.len();
-        now != old && now == 0
+        // import "source";
+        now != old && now == 0 && !self.has_side_effects(&src)
       }
 
       // export * from "source";
-      ModuleDecl::ExportAll(_) => false,
+      // the names it exports are unknown, so none of them can be kept
+      ModuleDecl::ExportAll(_) => self.mode == Mode::Keep,
 
       // import rust = go;
-      ModuleDecl::TsImportEquals(_) => panic!("invalid code"),
+      // import rust = require("source");
+      ModuleDecl::TsImportEquals(decl) => self.should_remove_decl_ident(&decl.id),
       // export = <expr>;
-      ModuleDecl::TsExportAssignment(_) => panic!("invalid code"),
+      ModuleDecl::TsExportAssignment(_) => false,
       // export as namespace Rust;
-      ModuleDecl::TsNamespaceExport(_) => panic!("invalid code"),
+      ModuleDecl::TsNamespaceExport(_) => false,
     }
   }
 }
@@ -158,72 +356,150 @@ impl VisitMut for RemoveVisitor {
   noop_visit_mut_type!();
 
   fn visit_mut_module_items(&mut self, n: &mut Vec<ModuleItem>) {
-    n.retain_mut(|x| match x {
-      ModuleItem::ModuleDecl(decl) => !self.should_remove_module_decl(decl),
-      ModuleItem::Stmt(stmt) => match stmt {
-        Stmt::Decl(decl) => match decl {
-          Decl::Class(c) => !self.should_remove_ident(&c.ident),
-          Decl::Fn(f) => !self.should_remove_ident(&f.ident),
-          Decl::Var(v) => {
-            v.decls
-              .retain_mut(|decl| !self.should_remove_pat(&mut decl.name));
-            !v.decls.is_empty()
-          }
-
-          Decl::Using(_) => panic!("invalid code"),
-          Decl::TsInterface(_) => panic!("invalid code"),
-          Decl::TsTypeAlias(_) => panic!("invalid code"),
-          Decl::TsEnum(_) => panic!("invalid code"),
-          Decl::TsModule(_) => panic!("invalid code"),
+    for mut item in std::mem::take(n) {
+      let remove = match &mut item {
+        ModuleItem::ModuleDecl(decl) => self.should_remove_module_decl(decl),
+        ModuleItem::Stmt(stmt) => match stmt {
+          Stmt::Decl(decl) => self.should_remove_decl(decl),
+          Stmt::Block(_)
+          | Stmt::Empty(_)
+          | Stmt::Debugger(_)
+          | Stmt::With(_)
+          | Stmt::Return(_)
+          | Stmt::Labeled(_)
+          | Stmt::Break(_)
+          | Stmt::Continue(_)
+          | Stmt::If(_)
+          | Stmt::Switch(_)
+          | Stmt::Throw(_)
+          | Stmt::Try(_)
+          | Stmt::While(_)
+          | Stmt::DoWhile(_)
+          | Stmt::For(_)
+          | Stmt::ForIn(_)
+          | Stmt::ForOf(_) => self.stmts.contains(&stmt.span()),
+          Stmt::Expr(x) => self.should_remove_expr_stmt(x),
         },
-        Stmt::Block(_) => true,
-        Stmt::Empty(_) => true,
-        Stmt::Debugger(_) => true,
-        Stmt::With(_) => true,
-        Stmt::Return(_) => true,
-        Stmt::Labeled(_) => true,
-        Stmt::Break(_) => true,
-        Stmt::Continue(_) => true,
-        Stmt::If(_) => true,
-        Stmt::Switch(_) => true,
-        Stmt::Throw(_) => true,
-        Stmt::Try(_) => true,
-        Stmt::While(_) => true,
-        Stmt::DoWhile(_) => true,
-        Stmt::For(_) => true,
-        Stmt::ForIn(_) => true,
-        Stmt::ForOf(_) => true,
-        Stmt::Expr(_) => true,
-      },
-    });
+      };
+      let placeholders = std::mem::take(&mut self.placeholders);
+      if remove {
+        self.removed_spans.push(item.span());
+        continue;
+      }
+
+      match item {
+        // export const { foo, ...rest } = obj;
+        // => const { foo, ...rest } = obj; export { rest };
+        ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(mut export)) if !placeholders.is_empty() => {
+          let Decl::Var(var) = &mut export.decl else {
+            unreachable!("placeholders are only created in patterns")
+          };
+          var.span = export.span;
+          let specifiers = var
+            .decls
+            .iter()
+            .flat_map(|decl| binding_idents(&decl.name))
+            .filter(|ident| !placeholders.contains(&ident.to_id()))
+            .map(|ident| {
+              ExportSpecifier::Named(ExportNamedSpecifier {
+                span: DUMMY_SP,
+                orig: ModuleExportName::Ident(ident),
+                exported: None,
+                is_type_only: false,
+              })
+            })
+            .collect();
+          n.push(ModuleItem::Stmt(Stmt::Decl(export.decl)));
+          n.push(ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(
+            NamedExport {
+              span: DUMMY_SP,
+              specifiers,
+              src: None,
+              type_only: false,
+              with: None,
+            },
+          )));
+        }
+        item => n.push(item),
+      }
+    }
   }
 }
 
+/// Every identifier bound by a pattern.
+fn binding_idents(n: &Pat) -> Vec<Ident> {
+  match n {
+    Pat::Ident(i) => vec![i.id.clone()],
+    Pat::Array(a) => a.elems.iter().flatten().flat_map(binding_idents).collect(),
+    Pat::Rest(r) => binding_idents(&r.arg),
+    Pat::Object(o) => o
+      .props
+      .iter()
+      .flat_map(|x| match x {
+        ObjectPatProp::KeyValue(kv) => binding_idents(&kv.value),
+        ObjectPatProp::Assign(a) => vec![a.key.id.clone()],
+        ObjectPatProp::Rest(r) => binding_idents(&r.arg),
+      })
+      .collect(),
+    Pat::Assign(a) => binding_idents(&a.left),
+    Pat::Invalid(_) | Pat::Expr(_) => vec![],
+  }
+}
+
+/// Result of [`RemoveVisitor::collect`].
+struct Collected<'a> {
+  /// Declarations to remove.
+  ids: HashSet<Id>,
+  /// Removed statements of `stmt_refs`, with their refs.
+  stmts: Vec<(Span, &'a HashSet<Id>)>,
+  /// Every removed statement.
+  removed: HashSet<Span>,
+  /// Removed exported declarations, which statements cannot keep alive.
+  forced: HashSet<Id>,
+}
+
 struct RefCounter<K> {
   map: HashMap<K, u32>,
+  /// Part of `map` held by removable statements.
+  weak: HashMap<K, u32>,
   done: HashSet<K>,
 }
 
 impl<K> RefCounter<K>
 where
-  K: Hash + Eq + Clone + Debug,
+  K: Hash + Eq + Clone,
 {
   fn from_keys(keys: Vec<K>) -> Self {
     let mut map = HashMap::new();
     for key in keys {
       map.entry(key).or_insert(0);
     }
+    let weak = HashMap::new();
     let done = HashSet::new();
-    Self { map, done }
+    Self { map, weak, done }
   }
 
   fn count(&mut self, key: &K) {
-    println!("count: {:?}", key);
     self.map.entry(key.clone()).and_modify(|x| *x += 1);
   }
 
+  fn count_weak(&mut self, key: &K) {
+    if self.map.contains_key(key) {
+      self.count(key);
+      *self.weak.entry(key.clone()).or_default() += 1;
+    }
+  }
+
   fn discount(&mut self, key: &K, f: impl FnOnce(&K)) {
-    println!("discount: {:?}", key);
+    self.release(key, false, f);
+  }
+
+  fn discount_weak(&mut self, key: &K, f: impl FnOnce(&K)) {
+    self.release(key, true, f);
+  }
+
+  /// Drop a reference, removes the key once only weak references are left.
+  fn release(&mut self, key: &K, weak: bool, f: impl FnOnce(&K)) {
     if self.done.contains(key) {
       return;
     }
@@ -234,7 +510,16 @@ where
         return;
       }
       *x -= 1;
-      if *x == 0 {
+      let x = *x;
+      let weak = match self.weak.get_mut(key) {
+        Some(w) if weak => {
+          *w -= 1;
+          *w
+        }
+        Some(w) => *w,
+        None => 0,
+      };
+      if x == weak {
         self.mark(key);
         f(key);
       }
@@ -247,7 +532,102 @@ where
 }
 
 impl RemoveVisitor {
-  pub fn new(imports: ImportVisitor, removes: Vec<String>) -> Self {
+  pub fn new(imports: ImportVisitor, matcher: &Matcher, mode: Mode) -> Self {
+    // every export name found in the module
+    // `export * from "source"` is not included
+    let exports = imports
+      .export_decls
+      .keys()
+      .chain(imports.export_refs.keys())
+      .chain(imports.export_froms.keys())
+      .cloned()
+      .collect::<BTreeSet<_>>();
+
+    let not_found = matcher.unmatched(exports.iter());
+
+    let removes = exports
+      .iter()
+      .filter(|name| match mode {
+        Mode::Remove => matcher.is_match(name),
+        Mode::Keep => !matcher.is_match(name),
+      })
+      .cloned()
+      .collect::<HashSet<_>>();
+
+    // statements that cannot be removed, because they use kept declarations
+    let mut pinned = HashSet::new();
+    let (ids, stmts) = loop {
+      let Collected {
+        ids,
+        stmts,
+        removed,
+        forced,
+      } = Self::collect(&imports, &removes, &pinned);
+      // statements using force-removed declarations are removed anyway,
+      // keeping them would leave a dangling reference
+      let stmts_kept = stmts.iter().filter(|(_, refs)| {
+        refs
+          .iter()
+          .any(|id| imports.decl_refs.contains_key(id) && !ids.contains(id))
+          && !refs.iter().any(|id| forced.contains(id))
+      });
+      // foo.bar = baz; where only `baz` is removed
+      let writes_kept = imports
+        .writes
+        .iter()
+        .filter(|(span, _, refs)| !removed.contains(span) && refs.iter().any(|id| ids.contains(id)))
+        .map(|(span, _, refs)| (*span, refs));
+      let kept = stmts_kept
+        .copied()
+        .chain(writes_kept)
+        .map(|(span, _)| span)
+        .filter(|span| !pinned.contains(span))
+        .collect::<Vec<_>>();
+      if kept.is_empty() {
+        let pure = imports.pure_stmts.iter().map(|(span, _)| *span);
+        // exports.foo = ...;
+        let commonjs = (imports.commonjs_exports.iter())
+          .filter(|(name, _)| removes.contains(*name))
+          .flat_map(|(_, spans)| spans.iter().copied());
+        break (
+          ids,
+          removed.into_iter().chain(pure).chain(commonjs).collect(),
+        );
+      }
+      pinned.extend(kept);
+    };
+
+    let removed_exports = exports
+      .into_iter()
+      .filter(|name| removes.contains(name))
+      .collect();
+
+    Self {
+      mode,
+      names: removes,
+      ids,
+      stmts,
+      commonjs_objects: imports.commonjs_objects,
+      import_froms: imports.imports,
+      placeholders: vec![],
+      side_effects: Matcher::default(),
+      side_effect_free: Matcher::default(),
+      annotations: imports.annotations,
+      removed_exports,
+      not_found,
+      removed_decls: BTreeSet::new(),
+      removed_imports: BTreeMap::new(),
+      removed_spans: vec![],
+    }
+  }
+
+  /// Find the declarations and statements to remove, `pinned` statements are
+  /// always kept.
+  fn collect<'a>(
+    imports: &'a ImportVisitor,
+    removes: &HashSet<String>,
+    pinned: &HashSet<Span>,
+  ) -> Collected<'a> {
     // analyze every keys refs counts
     let mut ref_counts = RefCounter::from_keys(imports.decl_refs.keys().cloned().collect());
 
@@ -261,24 +641,47 @@ impl RemoveVisitor {
     for value in &imports.global_refs {
       ref_counts.count(value);
     }
-    for (_, values) in &imports.export_refs {
+    for values in imports.export_refs.values() {
       for value in values {
         ref_counts.count(value);
       }
     }
-    for (_, value) in &imports.export_decls {
+    for value in imports.export_decls.values() {
       ref_counts.count(value);
     }
+    for (_, values) in &imports.pure_stmts {
+      for value in values {
+        ref_counts.count(value);
+      }
+    }
+    for (span, values) in &imports.stmt_refs {
+      for value in values {
+        match pinned.contains(span) {
+          true => ref_counts.count(value),
+          false => ref_counts.count_weak(value),
+        }
+      }
+    }
+    for (span, _, values) in &imports.writes {
+      for value in values {
+        match pinned.contains(span) {
+          true => ref_counts.count(value),
+          false => ref_counts.count_weak(value),
+        }
+      }
+    }
 
     // repeatly mark decls as should remove
     let mut queue = VecDeque::<Id>::new();
 
     // force-remove
     // export function foo() {}
+    let mut forced = HashSet::new();
     for (name, id) in &imports.export_decls {
       if removes.contains(name) {
         ref_counts.mark(id);
         queue.push_back(id.clone());
+        forced.insert(id.clone());
       }
     }
 
@@ -292,19 +695,73 @@ impl RemoveVisitor {
       }
     }
 
-    while let Some(decl) = queue.pop_front() {
-      if let Some(ids) = imports.decl_refs.get(&decl) {
-        for id in ids {
-          if id != &decl {
-            ref_counts.discount(id, |id| queue.push_back(id.clone()));
+    // pure statements are always removed
+    // /*#__PURE__*/ register(foo);
+    for (_, ids) in &imports.pure_stmts {
+      for id in ids {
+        ref_counts.discount(id, |id| queue.push_back(id.clone()))
+      }
+    }
+
+    // unused pure declarations
+    // const foo = /*#__PURE__*/ createFoo();
+    for id in &imports.pure_decls {
+      if ref_counts.map.get(id) == Some(&0) && !ref_counts.done.contains(id) {
+        ref_counts.mark(id);
+        queue.push_back(id.clone());
+      }
+    }
+
+    let mut stmts = vec![];
+    let mut removed = HashSet::new();
+    loop {
+      while let Some(decl) = queue.pop_front() {
+        if let Some(ids) = imports.decl_refs.get(&decl) {
+          for id in ids {
+            if id != &decl {
+              ref_counts.discount(id, |id| queue.push_back(id.clone()));
+            }
+          }
+        }
+      }
+
+      // remove statements using removed decls, and release what else they use
+      for (span, ids) in &imports.stmt_refs {
+        if pinned.contains(span) || removed.contains(span) {
+          continue;
+        }
+        if ids.iter().any(|id| ref_counts.done.contains(id)) {
+          removed.insert(*span);
+          stmts.push((*span, ids));
+          for id in ids {
+            ref_counts.discount_weak(id, |id| queue.push_back(id.clone()));
+          }
+        }
+      }
+
+      // remove writes to removed decls, or of force-removed ones
+      for (span, target, ids) in &imports.writes {
+        if pinned.contains(span) || removed.contains(span) {
+          continue;
+        }
+        if ref_counts.done.contains(target) || ids.iter().any(|id| forced.contains(id)) {
+          removed.insert(*span);
+          for id in ids {
+            ref_counts.discount_weak(id, |id| queue.push_back(id.clone()));
           }
         }
       }
+
+      if queue.is_empty() {
+        break;
+      }
     }
 
-    Self {
-      names: removes.into_iter().collect(),
+    Collected {
       ids: ref_counts.done,
+      stmts,
+      removed,
+      forced,
     }
   }
 }
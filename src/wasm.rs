@@ -0,0 +1,141 @@
+//! JavaScript bindings.
+
+use js_sys::Reflect;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{Error, Loader, Options};
+
+fn to_js_error(err: Error) -> JsValue {
+  let error = js_sys::Error::new(err.message());
+  let set = |key: &str, value: JsValue| {
+    Reflect::set(&error, &JsValue::from_str(key), &value).unwrap_throw();
+  };
+  set("code", JsValue::from_str(err.code()));
+  if let Some(loc) = err.location() {
+    set("start", JsValue::from(loc.utf16_start as u32));
+    set("end", JsValue::from(loc.utf16_end as u32));
+    set("line", JsValue::from(loc.line as u32));
+    set("column", JsValue::from(loc.column as u32));
+  }
+  error.into()
+}
+
+fn to_js_output(output: impl Serialize) -> Result<JsValue, JsValue> {
+  let serializer = serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+  output.serialize(&serializer).map_err(Into::into)
+}
+
+fn from_js_options(options: JsValue) -> Result<Options, JsValue> {
+  if options.is_undefined() || options.is_null() {
+    return Ok(Options::default());
+  }
+  serde_wasm_bindgen::from_value(options)
+    .map_err(|err| js_sys::TypeError::new(&format!("invalid options: {}", err)).into())
+}
+
+#[wasm_bindgen]
+pub fn remove_exports(source: &str, exports: Vec<String>) -> Result<String, JsValue> {
+  crate::remove_exports_with_options(source, exports, &Options::default())
+    .map(|output| output.code)
+    .map_err(to_js_error)
+}
+
+#[wasm_bindgen]
+pub fn remove_exports_with_options(
+  source: &str,
+  exports: Vec<String>,
+  options: JsValue,
+) -> Result<JsValue, JsValue> {
+  let options = from_js_options(options)?;
+  crate::remove_exports_with_options(source, exports, &options)
+    .map_err(to_js_error)
+    .and_then(to_js_output)
+}
+
+#[wasm_bindgen]
+pub fn analyze(source: &str, options: JsValue) -> Result<JsValue, JsValue> {
+  let options = from_js_options(options)?;
+  crate::analyze(source, &options)
+    .map_err(to_js_error)
+    .and_then(to_js_output)
+}
+
+/// A [`Loader`] calling the `resolve` and `load` functions of a JS object.
+struct JsLoader(JsValue);
+
+impl JsLoader {
+  fn call(&self, method: &str, args: &[&str]) -> Result<JsValue, String> {
+    let function = Reflect::get(&self.0, &JsValue::from_str(method))
+      .ok()
+      .and_then(|x| x.dyn_into::<js_sys::Function>().ok())
+      .ok_or_else(|| format!("loader.{} is not a function", method))?;
+    let args = args
+      .iter()
+      .map(|x| JsValue::from_str(x))
+      .collect::<js_sys::Array>();
+    function
+      .apply(&self.0, &args)
+      .map_err(|err| match err.dyn_ref::<js_sys::Error>() {
+        Some(err) => String::from(err.message()),
+        None => format!("{:?}", err),
+      })
+  }
+}
+
+impl Loader for JsLoader {
+  fn resolve(&self, specifier: &str, importer: &str) -> Result<Option<String>, String> {
+    let id = self.call("resolve", &[specifier, importer])?;
+    match id.is_undefined() || id.is_null() {
+      true => Ok(None),
+      false => id
+        .as_string()
+        .map(Some)
+        .ok_or_else(|| "loader.resolve must return a string".to_string()),
+    }
+  }
+
+  fn load(&self, id: &str) -> Result<String, String> {
+    self
+      .call("load", &[id])?
+      .as_string()
+      .ok_or_else(|| "loader.load must return a string".to_string())
+  }
+}
+
+#[wasm_bindgen]
+pub fn remove_exports_from_graph(
+  entry: &str,
+  exports: Vec<String>,
+  options: JsValue,
+  loader: JsValue,
+) -> Result<JsValue, JsValue> {
+  let options = from_js_options(options)?;
+  crate::remove_exports_from_graph(entry, exports, &options, &JsLoader(loader))
+    .map_err(to_js_error)
+    .and_then(to_js_output)
+}
+
+/// Removes exports from many modules, caching their analysis between runs.
+#[wasm_bindgen(js_name = Batch)]
+pub struct JsBatch(crate::Batch);
+
+#[wasm_bindgen(js_class = Batch)]
+impl JsBatch {
+  #[wasm_bindgen(constructor)]
+  pub fn new(options: JsValue) -> Result<JsBatch, JsValue> {
+    Ok(Self(crate::Batch::new(from_js_options(options)?)))
+  }
+
+  /// Outputs of `jobs` in the same order, or the errors they failed with.
+  pub fn run(&self, jobs: JsValue) -> Result<js_sys::Array, JsValue> {
+    let jobs: Vec<crate::Job> = serde_wasm_bindgen::from_value(jobs)
+      .map_err(|err| js_sys::TypeError::new(&format!("invalid jobs: {}", err)))?;
+    self
+      .0
+      .run(&jobs)
+      .into_iter()
+      .map(|result| result.map_or_else(|err| Ok(to_js_error(err)), to_js_output))
+      .collect()
+  }
+}